@@ -0,0 +1,160 @@
+use crate::{
+    unsupported_version,
+    v2::decryptor::password_bytes,
+    v3::{
+        decryptor::{authentication_failed, unexpected_options},
+        errors::{Error, ErrorKind, Result},
+        header::Header,
+        types::*,
+    },
+    Version,
+};
+use aes::{
+    cipher::{
+        block_padding::Pkcs7, generic_array::GenericArray, BlockDecryptMut, BlockEncrypt, KeyInit,
+        KeyIvInit,
+    },
+    Aes256,
+};
+use zeroize::Zeroizing;
+
+type Aes256CbcDec = cbc::Decryptor<Aes256>;
+
+const BLOCK_SIZE: usize = 16;
+
+/// Reads the version of a legacy message, rejecting anything newer than v1.
+fn legacy_version(message: &[u8]) -> Result<Version> {
+    match Version::of(message)? {
+        version @ (Version::V0 | Version::V1) => Ok(version),
+        version => Err(unsupported_version(version as u8)),
+    }
+}
+
+/// A read-only "Decryptor" for v0 and v1 messages. Both share the v3 header layout, but
+/// authenticate the cipher text alone (the header isn't covered by the HMAC) and derive the
+/// keys with the same password truncation bug as v2.
+///
+/// v0 encrypts with AES-256 in CTR mode (no padding), v1 with AES-256-CBC and PKCS7 padding.
+#[derive(Debug)]
+pub struct Decryptor {
+    pub version: Version,
+    header: Header,
+    encryption_key: EncryptionKey,
+    hmac_key: HMACKey,
+}
+
+impl Decryptor {
+    /// Builds a "Decryptor" out of a password and a message (to decrypt).
+    pub fn from<P: Into<Password>>(password: P, message: &[u8]) -> Result<Decryptor> {
+        let version = legacy_version(message)?;
+        let header = Header::parse_version(message, version as u8)?;
+        let password = password.into();
+        let password = password_bytes(&password);
+        let (encryption_key, hmac_key) = match header {
+            Header::Password {
+                ref encryption_salt,
+                ref hmac_salt,
+                ..
+            } => (
+                EncryptionKey::new(encryption_salt, password),
+                HMACKey::new(hmac_salt, password),
+            ),
+            Header::Key { .. } => return Err(unexpected_options(&header)),
+        };
+
+        Ok(Decryptor {
+            version,
+            header,
+            encryption_key,
+            hmac_key,
+        })
+    }
+
+    /// Builds a "Decryptor" out of an `EncryptionKey`, an `HMACKey` and a message (to decrypt)
+    /// encrypted with the key-based API.
+    pub fn from_keys(
+        encryption_key: EncryptionKey,
+        hmac_key: HMACKey,
+        message: &[u8],
+    ) -> Result<Decryptor> {
+        let version = legacy_version(message)?;
+        let header = Header::parse_version(message, version as u8)?;
+        if let Header::Password { .. } = header {
+            return Err(unexpected_options(&header));
+        }
+
+        Ok(Decryptor {
+            version,
+            header,
+            encryption_key,
+            hmac_key,
+        })
+    }
+
+    /// Decrypts a `cipher_text`, returning a `Message` or an `Error`. The HMAC is validated
+    /// first and any failure is reported as `ErrorKind::AuthenticationFailed`.
+    pub fn decrypt(&self, cipher_text: &[u8]) -> Result<Message> {
+        let header_len = self.header.encoded_len();
+        if cipher_text.len() < header_len + HMAC_LEN {
+            return Err(Error::new(
+                ErrorKind::HMACNotFound,
+                "Decryption failed, the HMAC is missing or truncated.".to_owned(),
+            ));
+        }
+
+        let hmac_position = cipher_text.len() - HMAC_LEN;
+        let encrypted = &cipher_text[header_len..hmac_position];
+
+        let hmac = HMAC::from_slice(&cipher_text[hmac_position..])?;
+        let computed_hmac = HMAC::over(&[], encrypted, &self.hmac_key)?;
+        if !hmac.is_equal_in_consistent_time_to(&computed_hmac) {
+            return Err(authentication_failed());
+        }
+
+        match self.version {
+            Version::V0 => Ok(self.plain_text_ctr(encrypted)),
+            _ => self.plain_text_cbc(encrypted),
+        }
+    }
+
+    fn plain_text_cbc(&self, cipher_text: &[u8]) -> Result<Message> {
+        let decryptor = Aes256CbcDec::new(
+            self.encryption_key.as_slice().into(),
+            self.header.iv().as_slice().into(),
+        );
+
+        let mut decrypted = Zeroizing::new(cipher_text.to_vec());
+        let len = decryptor
+            .decrypt_padded_mut::<Pkcs7>(&mut decrypted)
+            .map_err(|_| authentication_failed())?
+            .len();
+        decrypted.truncate(len);
+
+        Ok(std::mem::take(&mut *decrypted))
+    }
+
+    /// AES-256-CTR, with the `IV` as the initial (big-endian, 128 bits) counter block.
+    fn plain_text_ctr(&self, cipher_text: &[u8]) -> Message {
+        let cipher = Aes256::new(self.encryption_key.as_slice().into());
+        let mut counter = u128::from_be_bytes(
+            self.header
+                .iv()
+                .as_slice()
+                .try_into()
+                .expect("an IV is always 16 bytes long"),
+        );
+
+        let mut decrypted = cipher_text.to_vec();
+        for chunk in decrypted.chunks_mut(BLOCK_SIZE) {
+            let mut key_stream = GenericArray::from(counter.to_be_bytes());
+            cipher.encrypt_block(&mut key_stream);
+            chunk
+                .iter_mut()
+                .zip(key_stream.iter())
+                .for_each(|(byte, key)| *byte ^= key);
+            counter = counter.wrapping_add(1);
+        }
+
+        decrypted
+    }
+}
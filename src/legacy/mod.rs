@@ -0,0 +1,30 @@
+/// "Low-level" decryption abstractions.
+pub mod decryptor;
+
+use crate::v3::{
+    errors::Result,
+    types::{EncryptionKey, HMACKey, Message, Password},
+};
+use decryptor::Decryptor;
+
+/// Decrypts a v0 or v1 `Message` with the given password, producing either a decrypted
+/// `Vec<u8>` or an `Error` otherwise.
+///
+/// **Note: This is NOT a streaming function.**
+pub fn decrypt<P: Into<Password>>(password: P, message: &Message) -> Result<Vec<u8>> {
+    let decryptor = Decryptor::from(password, message)?;
+    decryptor.decrypt(message)
+}
+
+/// Decrypts a v0 or v1 `Message` produced by the key-based API with the given `EncryptionKey`
+/// and `HMACKey`, producing either a decrypted `Vec<u8>` or an `Error` otherwise.
+///
+/// **Note: This is NOT a streaming function.**
+pub fn decrypt_with_keys(
+    encryption_key: EncryptionKey,
+    hmac_key: HMACKey,
+    message: &Message,
+) -> Result<Vec<u8>> {
+    let decryptor = Decryptor::from_keys(encryption_key, hmac_key, message)?;
+    decryptor.decrypt(message)
+}
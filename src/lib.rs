@@ -8,11 +8,15 @@ format by [Rob Napier](https://twitter.com/cocoaphony). It currently supports
 to be written with a clean, easy to use API which matches the abstract pseudocode
 found in the spec.
 
+Messages in the older [Version 2](https://github.com/RNCryptor/RNCryptor-Spec/blob/master/RNCryptor-Spec-v2.md)
+format can be read and written through the `v2` module, which mirrors the API of `v3`. The
+even older v0 and v1 formats are supported, for decryption only, by the `legacy` module.
+
 ## Simple Usage
 It's likely you want to dive straight into how to use the library for encryption and decryption
 of data, which can be done easily with the two functions `encrypt` and `decrypt`. **Note that
 these are not streaming functions and will try to load the entire content to encrypt/decrypt
-into memory, which might not be what you want. Streaming functions are in the works (PRs welcome!)**
+into memory, which might not be what you want. See "Streaming" below for the alternative.**
 
 To encrypt something, simply call `encrypt`:
 
@@ -33,6 +37,16 @@ let encrypted = ... // A `Message` as encrypted by "encrypt".
 let plain_text = v3::decrypt("password", &encrypted));
 ```
 
+Should the version of the messages to decrypt not be known up front, the crate-level `decrypt`
+and `decrypt_with_keys` inspect the version byte and dispatch to the right implementation:
+
+```ignore
+extern crate rncryptor;
+
+let plain_text = rncryptor::decrypt("password", &encrypted)?;
+let encrypted = rncryptor::encrypt(rncryptor::Version::V3, "password", &plain_text)?;
+```
+
 ## Advanced Usage
 Sometimes you might want to have more control over the encryption/decryption process, and that's where the
 `Encryptor` and `Decryptor` data structures come into play, as they allow to fine-tune things like the `Salt`,
@@ -43,12 +57,11 @@ details).
 extern crate rncryptor;
 
 use rncryptor::v3::encryptor::Encryptor;
-use rustc_serialize::hex::FromHex;
 use rncryptor::v3::types::*;
 
-let encryption_salt = Salt("0203040506070001".from_hex().unwrap());
-let hmac_salt = Salt("0304050607080102".from_hex().unwrap());
-let iv = IV::from("0405060708090a0b0c0d0e0f00010203".from_hex().unwrap());
+let encryption_salt = Salt::from_hex("0203040506070001").unwrap();
+let hmac_salt = Salt::from_hex("0304050607080102").unwrap();
+let iv = IV::from_hex("0405060708090a0b0c0d0e0f00010203").unwrap();
 let plain_text = (0..).take(1_000_000).collect::<Vec<_>>();
 let e = Encryptor::from_password("thepassword", encryption_salt, hmac_salt, iv);
 match e {
@@ -57,6 +70,203 @@ match e {
 }
 ```
 
+An `Encryptor` is bound to its `IV` and must only encrypt a single message. To encrypt many
+messages with the same keys or password, build a `v3::encryptor::ReusableEncryptor` once instead:
+it draws a fresh `IV` (and, depending on how it was built, fresh salts) for every message.
+
+Salts and `IV`s are drawn from `rand::rngs::OsRng` by default. The `_with_rng` variants (e.g.
+`v3::encrypt_with_rng`, `Salt::new_with_rng`) take any `CryptoRng + RngCore` instead, such as a
+seeded RNG for reproducible tests, or the platform's RNG on embedded targets.
+
+With the `parallel` cargo feature, large messages are decrypted on the rayon thread pool, once
+their HMAC has been validated.
+
+## Streaming
+Large inputs can be encrypted without buffering them in memory by wrapping any `io::Write` into
+an `EncryptingWriter`, obtained from an `Encryptor`. Don't forget to call `finish`, which writes
+the last block and the HMAC.
+
+```ignore
+extern crate rncryptor;
+
+use rncryptor::v3::{encryptor::Encryptor, types::*};
+use std::io;
+
+let e = Encryptor::from_password("thepassword", Salt::new()?, Salt::new()?, IV::new()?)?;
+let mut writer = e.writer(File::create("backup.bin")?)?;
+io::copy(&mut File::open("backup.tar")?, &mut writer)?;
+writer.finish()?;
+```
+
+Code written against the Swift `update(withData:)`/`finalData()` API can use an
+`EncryptionContext` (from `Encryptor::context`) instead, which hands the encrypted bytes back
+rather than writing them anywhere.
+
+With the `tokio` cargo feature, `v3::async_tokio` provides the `AsyncEncryptWriter` and
+`AsyncDecryptReader` counterparts, which run the key derivation on tokio's blocking thread pool.
+The `futures-io` feature provides the same adapters for the `futures` traits in
+`v3::async_futures`, for async-std, smol and the like.
+
+Messages stored in a `Read + Seek` input (e.g. a file) can be read at arbitrary offsets through a
+`v3::seekable::SeekableDecryptor`, which only decrypts the blocks it needs.
+
+Many small key-based messages can be sent over a byte stream with the `v3::codec::MessageCodec`
+(behind the `codec` feature), a `tokio_util` codec framing each message with a length prefix.
+
+Decryption works the other way around, by wrapping any `io::Read` into a `DecryptingReader`. As the
+HMAC comes last, the plain text read is only authenticated once the reader hits the end of the
+message: should the validation fail, the last `read` returns an error and everything read so far
+must be thrown away.
+
+```ignore
+extern crate rncryptor;
+
+use rncryptor::v3::reader::DecryptingReader;
+use std::io;
+
+let mut reader = DecryptingReader::from_password("thepassword", File::open("backup.bin")?)?;
+io::copy(&mut reader, &mut File::create("backup.tar")?)?;
+```
+
 */
 
+pub mod legacy;
+pub mod v2;
 pub mod v3;
+
+use v3::{
+    errors::{Error, ErrorKind, Result},
+    types::{EncryptionKey, HMACKey, Message, Password, PlainText},
+};
+
+/// The versions of the RNCryptor format, as found in the first byte of every message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Version {
+    V0 = 0,
+    V1 = 1,
+    V2 = 2,
+    V3 = 3,
+}
+
+impl Version {
+    /// The version new messages should be written in.
+    pub const LATEST: Version = Version::V3;
+
+    /// Reads the `Version` of a message out of its first byte.
+    pub fn of(message: &[u8]) -> Result<Version> {
+        match message.first() {
+            None => Err(Error::new(
+                ErrorKind::NotEnoughInput(0),
+                "The message is empty.".to_owned(),
+            )),
+            Some(&version) => Version::try_from(version),
+        }
+    }
+
+    /// Whether this crate can write messages in this `Version`.
+    pub fn can_encrypt(self) -> bool {
+        matches!(self, Version::V2 | Version::V3)
+    }
+}
+
+impl Default for Version {
+    fn default() -> Version {
+        Version::LATEST
+    }
+}
+
+impl TryFrom<u8> for Version {
+    type Error = Error;
+
+    fn try_from(version: u8) -> Result<Version> {
+        match version {
+            0 => Ok(Version::V0),
+            1 => Ok(Version::V1),
+            2 => Ok(Version::V2),
+            3 => Ok(Version::V3),
+            _ => Err(unsupported_version(version)),
+        }
+    }
+}
+
+fn unsupported_version(version: u8) -> Error {
+    Error::new(
+        ErrorKind::UnsupportedVersion(version),
+        format!("Unsupported version {}.", version),
+    )
+}
+
+/// Encrypts a `PlainText` with the given password in the given `Version` of the format.
+///
+/// **Note: This is NOT a streaming function.**
+pub fn encrypt<P: Into<Password>>(
+    version: Version,
+    password: P,
+    plain_text: &PlainText,
+) -> Result<Message> {
+    match version {
+        Version::V2 => v2::encrypt(password, plain_text),
+        Version::V3 => v3::encrypt(password, plain_text),
+        _ => Err(unsupported_version(version as u8)),
+    }
+}
+
+/// Encrypts a `PlainText` with the given keys and a random `IV` in the given `Version` of the
+/// format.
+///
+/// **Note: This is NOT a streaming function.**
+pub fn encrypt_with_keys(
+    version: Version,
+    encryption_key: EncryptionKey,
+    hmac_key: HMACKey,
+    plain_text: &PlainText,
+) -> Result<Message> {
+    match version {
+        Version::V2 => v2::encryptor::Encryptor::from_keys_with_fixed_iv(
+            encryption_key,
+            hmac_key,
+            v3::types::IV::new()?,
+        )?
+        .encrypt(plain_text),
+        Version::V3 => v3::encryptor::ReusableEncryptor::from_keys(encryption_key, hmac_key)
+            .encrypt(plain_text),
+        _ => Err(unsupported_version(version as u8)),
+    }
+}
+
+/// Decrypts a `Message` of any supported `Version` with the given password, dispatching on its
+/// version byte.
+///
+/// **Note: This is NOT a streaming function.**
+pub fn decrypt<P: Into<Password>>(password: P, message: &[u8]) -> Result<Vec<u8>> {
+    match Version::of(message)? {
+        Version::V0 | Version::V1 => {
+            legacy::decryptor::Decryptor::from(password, message)?.decrypt(message)
+        }
+        Version::V2 => v2::decryptor::Decryptor::from(password, message)?.decrypt(message),
+        Version::V3 => v3::decryptor::Decryptor::from(password, message)?.decrypt(message),
+    }
+}
+
+/// Decrypts a `Message` of any supported `Version` produced by the key-based API, dispatching
+/// on its version byte.
+///
+/// **Note: This is NOT a streaming function.**
+pub fn decrypt_with_keys(
+    encryption_key: EncryptionKey,
+    hmac_key: HMACKey,
+    message: &[u8],
+) -> Result<Vec<u8>> {
+    match Version::of(message)? {
+        Version::V0 | Version::V1 => {
+            legacy::decryptor::Decryptor::from_keys(encryption_key, hmac_key, message)?
+                .decrypt(message)
+        }
+        Version::V2 => {
+            v2::decryptor::Decryptor::from_keys(encryption_key, hmac_key, message)?.decrypt(message)
+        }
+        Version::V3 => {
+            v3::decryptor::Decryptor::from_keys(encryption_key, hmac_key, message)?.decrypt(message)
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use crate::v3::{
+    decryptor::{unexpected_options, Decryptor as V3Decryptor},
+    errors::Result,
+    header::Header,
+    types::*,
+};
+
+/// The version byte of every v2 message.
+pub const VERSION: u8 = 2;
+
+/// A v2 "Decryptor". The v2 format shares its layout (header, AES-256-CBC and HMAC-SHA256) with
+/// v3, so this is a thin layer over the v3 machinery: what sets the two apart is the version
+/// byte, and how v2 derives its keys out of a password.
+#[derive(Debug)]
+pub struct Decryptor(V3Decryptor);
+
+impl Decryptor {
+    /// Builds a "Decryptor" out of a password and a message (to decrypt).
+    ///
+    /// The keys are derived the way the v2 implementations did it, bug included: see
+    /// `password_bytes`.
+    pub fn from<P: Into<Password>>(password: P, message: &[u8]) -> Result<Decryptor> {
+        let header = Header::parse_version(message, VERSION)?;
+        let password = password.into();
+        let password = password_bytes(&password);
+        let (encryption_key, hmac_key) = match header {
+            Header::Password {
+                ref encryption_salt,
+                ref hmac_salt,
+                ..
+            } => (
+                EncryptionKey::new(encryption_salt, password),
+                HMACKey::new(hmac_salt, password),
+            ),
+            Header::Key { .. } => return Err(unexpected_options(&header)),
+        };
+
+        Ok(Decryptor(V3Decryptor::new(
+            VERSION,
+            header,
+            encryption_key,
+            hmac_key,
+        )))
+    }
+
+    /// Builds a "Decryptor" out of an `EncryptionKey`, an `HMACKey` and a message (to decrypt)
+    /// encrypted with the key-based API.
+    pub fn from_keys(
+        encryption_key: EncryptionKey,
+        hmac_key: HMACKey,
+        message: &[u8],
+    ) -> Result<Decryptor> {
+        let header = Header::parse_version(message, VERSION)?;
+        if let Header::Password { .. } = header {
+            return Err(unexpected_options(&header));
+        }
+
+        Ok(Decryptor(V3Decryptor::new(
+            VERSION,
+            header,
+            encryption_key,
+            hmac_key,
+        )))
+    }
+
+    /// Decrypts a `cipher_text`, returning a `Message` or an `Error`. As in v3, the HMAC is
+    /// validated first and any failure is reported as `ErrorKind::AuthenticationFailed`.
+    pub fn decrypt(&self, cipher_text: &[u8]) -> Result<Message> {
+        self.0.decrypt(cipher_text)
+    }
+
+    /// Like `decrypt`, but reports the detailed cause of a failure. **For debugging only.**
+    pub fn decrypt_with_diagnostics(&self, cipher_text: &[u8]) -> Result<Message> {
+        self.0.decrypt_with_diagnostics(cipher_text)
+    }
+}
+
+/// The bytes of `password` the v2 key derivation actually used.
+///
+/// The v2 implementations passed the password's length in *characters* (UTF-16 code units, as
+/// returned by `NSString.length`) alongside its UTF-8 bytes, so any password with multi-byte
+/// characters got truncated. This reproduces the truncation, so that such messages decrypt.
+pub fn password_bytes(password: &Password) -> &[u8] {
+    let bytes = password.as_bytes();
+    match std::str::from_utf8(bytes) {
+        Ok(text) => &bytes[..text.encode_utf16().count()],
+        Err(_) => bytes,
+    }
+}
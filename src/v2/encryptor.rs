@@ -0,0 +1,84 @@
+use super::decryptor::{password_bytes, VERSION};
+use crate::v3::{
+    context::EncryptionContext,
+    encryptor::Encryptor as V3Encryptor,
+    errors::{Error, ErrorKind, Result},
+    header::Header,
+    types::*,
+    writer::EncryptingWriter,
+};
+use std::io::Write;
+
+/// A v2 "Encryptor", for the clients which can't read v3 yet. Like the v2 `Decryptor`, it is a
+/// thin layer over the v3 machinery, writing a version byte of 2 and deriving the keys the way
+/// the v2 implementations did.
+#[derive(Clone, Debug)]
+pub struct Encryptor(V3Encryptor);
+
+impl Encryptor {
+    /// Builds an "Encryptor" out of a password, both salts and an `IV`.
+    ///
+    /// The keys are derived out of the truncated password bytes (see `password_bytes`), so that
+    /// the v2 implementations derive the very same keys when decrypting.
+    pub fn from_password<P: Into<Password>>(
+        password: P,
+        es: EncryptionSalt,
+        hs: HMACSalt,
+        iv: IV,
+    ) -> Result<Encryptor> {
+        let password = password.into();
+        if password.is_empty() {
+            return Err(Error::new(
+                ErrorKind::WrongInputSize(0),
+                "Password length cannot be empty.".to_owned(),
+            ));
+        }
+        let password = password_bytes(&password);
+        let encryption_key = EncryptionKey::new(&es, password);
+        let hmac_key = HMACKey::new(&hs, password);
+
+        Ok(Encryptor(V3Encryptor::new(
+            VERSION,
+            Header::Password {
+                encryption_salt: es,
+                hmac_salt: hs,
+                iv,
+            },
+            encryption_key,
+            hmac_key,
+        )))
+    }
+
+    /// Builds an "Encryptor" out of an `EncryptionKey`, an `HMACKey` and a given `IV`. As with
+    /// v3, an "Encryptor" must only ever encrypt a single message.
+    pub fn from_keys_with_fixed_iv(ek: EncryptionKey, hk: HMACKey, iv: IV) -> Result<Encryptor> {
+        Ok(Encryptor(V3Encryptor::new(
+            VERSION,
+            Header::Key { iv },
+            ek,
+            hk,
+        )))
+    }
+
+    #[deprecated(
+        note = "renamed to `from_keys_with_fixed_iv`: never encrypt more than one message"
+    )]
+    pub fn from_keys(ek: EncryptionKey, hk: HMACKey, iv: IV) -> Result<Encryptor> {
+        Encryptor::from_keys_with_fixed_iv(ek, hk, iv)
+    }
+
+    /// Encrypts a `plain_text` into a complete v2 `Message`.
+    pub fn encrypt(&self, plain_text: &PlainText) -> Result<Message> {
+        self.0.encrypt(plain_text)
+    }
+
+    /// Starts a push-style `EncryptionContext`, writing a v2 message.
+    pub fn context(&self) -> Result<EncryptionContext> {
+        self.0.context()
+    }
+
+    /// Wraps `inner` into an `EncryptingWriter`, which streams the encrypted v2 message into it.
+    pub fn writer<W: Write>(&self, inner: W) -> Result<EncryptingWriter<W>> {
+        self.0.writer(inner)
+    }
+}
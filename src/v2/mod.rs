@@ -0,0 +1,45 @@
+/// "Low-level" decryption abstractions.
+pub mod decryptor;
+/// "Low-level" encryption abstractions.
+pub mod encryptor;
+
+use crate::v3::{
+    errors::Result,
+    types::{EncryptionKey, HMACKey, Message, Password, PlainText, Salt, IV},
+};
+use decryptor::Decryptor;
+use encryptor::Encryptor;
+
+/// Encrypts a `PlainText` with the given password into a v2 `Message`, for the clients which
+/// can't read v3.
+///
+/// **Note: This is NOT a streaming function.**
+pub fn encrypt<P: Into<Password>>(password: P, plain_text: &PlainText) -> Result<Message> {
+    let esalt = Salt::new()?;
+    let hsalt = Salt::new()?;
+    let iv = IV::new()?;
+    let encryptor = Encryptor::from_password(password, esalt, hsalt, iv)?;
+    encryptor.encrypt(plain_text)
+}
+
+/// Decrypts a v2 `Message` with the given password, producing either a decrypted
+/// `Vec<u8>` or an `Error` otherwise.
+///
+/// **Note: This is NOT a streaming function.**
+pub fn decrypt<P: Into<Password>>(password: P, message: &Message) -> Result<Vec<u8>> {
+    let decryptor = Decryptor::from(password, message)?;
+    decryptor.decrypt(message)
+}
+
+/// Decrypts a v2 `Message` produced by the key-based API with the given `EncryptionKey` and
+/// `HMACKey`, producing either a decrypted `Vec<u8>` or an `Error` otherwise.
+///
+/// **Note: This is NOT a streaming function.**
+pub fn decrypt_with_keys(
+    encryption_key: EncryptionKey,
+    hmac_key: HMACKey,
+    message: &Message,
+) -> Result<Vec<u8>> {
+    let decryptor = Decryptor::from_keys(encryption_key, hmac_key, message)?;
+    decryptor.decrypt(message)
+}
@@ -0,0 +1,134 @@
+use super::{
+    context::{DecryptionContext, EncryptionContext},
+    reader::advance,
+};
+use std::{
+    io,
+    task::{ready, Poll},
+};
+use zeroize::Zeroizing;
+
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// The runtime-agnostic half of the asynchronous encrypting writers: each runtime's adapter
+/// only supplies how to poll its wrapped writer.
+pub(super) struct Encrypting {
+    context: Option<EncryptionContext>,
+    output: Vec<u8>,
+    written: usize,
+}
+
+impl Encrypting {
+    pub(super) fn new(context: EncryptionContext) -> Encrypting {
+        Encrypting {
+            context: Some(context),
+            output: Vec::new(),
+            written: 0,
+        }
+    }
+
+    /// Encrypts `buf`, once whatever was output before has been written out.
+    pub(super) fn poll_write<F>(&mut self, buf: &[u8], write: F) -> Poll<io::Result<usize>>
+    where
+        F: FnMut(&[u8]) -> Poll<io::Result<usize>>,
+    {
+        ready!(self.poll_drain(write))?;
+        match self.context {
+            Some(ref mut context) => context.update_into(buf, &mut self.output),
+            None => {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::BrokenPipe,
+                    "The message has already been completed.",
+                )))
+            }
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    /// Writes out whatever was output so far.
+    pub(super) fn poll_drain<F>(&mut self, mut write: F) -> Poll<io::Result<()>>
+    where
+        F: FnMut(&[u8]) -> Poll<io::Result<usize>>,
+    {
+        while self.written < self.output.len() {
+            let written = ready!(write(&self.output[self.written..]))?;
+            if written == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.written += written;
+        }
+        self.output.clear();
+        self.written = 0;
+        Poll::Ready(Ok(()))
+    }
+
+    /// Completes the message, writing out the padded last block and the HMAC.
+    pub(super) fn poll_finish<F>(&mut self, mut write: F) -> Poll<io::Result<()>>
+    where
+        F: FnMut(&[u8]) -> Poll<io::Result<usize>>,
+    {
+        ready!(self.poll_drain(&mut write))?;
+        if let Some(context) = self.context.take() {
+            context.finalize_into(&mut self.output);
+        }
+        self.poll_drain(write)
+    }
+}
+
+/// The runtime-agnostic half of the asynchronous decrypting readers: each runtime's adapter
+/// only supplies how to poll its wrapped reader.
+pub(super) struct Decrypting {
+    context: Option<DecryptionContext>,
+    plain_text: Zeroizing<Vec<u8>>,
+    position: usize,
+    failed: bool,
+}
+
+impl Decrypting {
+    pub(super) fn new(context: DecryptionContext) -> Decrypting {
+        Decrypting {
+            context: Some(context),
+            plain_text: Zeroizing::default(),
+            position: 0,
+            failed: false,
+        }
+    }
+
+    /// Fills `buf` with plain text, reading cipher text through `read` when there's none left.
+    pub(super) fn poll_read<F>(&mut self, buf: &mut [u8], mut read: F) -> Poll<io::Result<usize>>
+    where
+        F: FnMut(&mut [u8]) -> Poll<io::Result<usize>>,
+    {
+        while self.position == self.plain_text.len() {
+            if self.failed {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Decryption previously failed.",
+                )));
+            }
+            if self.context.is_none() {
+                return Poll::Ready(Ok(0));
+            }
+
+            let mut chunk = [0; CHUNK_SIZE];
+            let filled = ready!(read(&mut chunk))?;
+            match advance(&mut self.context, &chunk[..filled]) {
+                Ok(plain_text) => {
+                    self.plain_text = plain_text;
+                    self.position = 0;
+                }
+                Err(e) => {
+                    self.failed = true;
+                    self.plain_text = Zeroizing::default();
+                    return Poll::Ready(Err(e.into()));
+                }
+            }
+        }
+
+        let available = &self.plain_text[self.position..];
+        let read = available.len().min(buf.len());
+        buf[..read].copy_from_slice(&available[..read]);
+        self.position += read;
+        Poll::Ready(Ok(read))
+    }
+}
@@ -0,0 +1,162 @@
+use super::{
+    adapter::{Decrypting, Encrypting},
+    context::DecryptionContext,
+    decryptor::Decryptor,
+    encryptor::Encryptor,
+    errors::{Error, ErrorKind, Result},
+    header::{Header, PASSWORD_HEADER_LEN},
+    types::*,
+};
+use futures_io::{AsyncRead, AsyncWrite};
+use std::{
+    future, io,
+    pin::Pin,
+    task::{self, ready, Poll},
+};
+
+/// An `AsyncWrite` adapter which encrypts everything written into it, streaming the resulting
+/// RNCryptor message into the wrapped writer: the runtime-agnostic `EncryptingWriter`.
+///
+/// The message is completed by `close`, which writes the padded last block and the HMAC before
+/// closing the wrapped writer: dropping the writer without closing it leaves a truncated (and
+/// therefore invalid) message behind.
+///
+/// There's no runtime-agnostic way to offload the (slow) password-based key derivation, so
+/// build the `Encryptor` on your runtime's blocking thread pool.
+pub struct AsyncEncryptWriter<W> {
+    inner: W,
+    state: Encrypting,
+}
+
+impl<W: AsyncWrite + Unpin> AsyncEncryptWriter<W> {
+    /// Wraps `inner`, encrypting with the given `Encryptor`.
+    pub fn new(encryptor: &Encryptor, inner: W) -> Result<AsyncEncryptWriter<W>> {
+        Ok(AsyncEncryptWriter {
+            inner,
+            state: Encrypting::new(encryptor.context()?),
+        })
+    }
+
+    /// Gets a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for AsyncEncryptWriter<W> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let AsyncEncryptWriter { inner, state } = self.get_mut();
+        state.poll_write(buf, |output| Pin::new(&mut *inner).poll_write(cx, output))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        let AsyncEncryptWriter { inner, state } = self.get_mut();
+        ready!(state.poll_drain(|output| Pin::new(&mut *inner).poll_write(cx, output)))?;
+        Pin::new(inner).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        let AsyncEncryptWriter { inner, state } = self.get_mut();
+        ready!(state.poll_finish(|output| Pin::new(&mut *inner).poll_write(cx, output)))?;
+        Pin::new(inner).poll_close(cx)
+    }
+}
+
+/// An `AsyncRead` adapter which decrypts a RNCryptor message while reading it: the
+/// runtime-agnostic `DecryptingReader`.
+///
+/// As with the `DecryptingReader`, plain text is handed out **before** it has been
+/// authenticated: a failed validation is reported by the final read, and everything read until
+/// then must be discarded by the caller.
+pub struct AsyncDecryptReader<R> {
+    inner: R,
+    state: Decrypting,
+}
+
+impl<R: AsyncRead + Unpin> AsyncDecryptReader<R> {
+    /// Wraps `inner`, decrypting with the given `DecryptionContext`.
+    pub fn new(context: DecryptionContext, inner: R) -> AsyncDecryptReader<R> {
+        AsyncDecryptReader {
+            inner,
+            state: Decrypting::new(context),
+        }
+    }
+
+    /// Wraps `inner`, which holds a message produced by the key-based API.
+    pub fn with_keys(
+        encryption_key: EncryptionKey,
+        hmac_key: HMACKey,
+        inner: R,
+    ) -> AsyncDecryptReader<R> {
+        AsyncDecryptReader::new(
+            DecryptionContext::with_keys(encryption_key, hmac_key),
+            inner,
+        )
+    }
+
+    /// Reads the header out of `inner` and derives the keys from `password`. **The derivation
+    /// blocks the current task** until it's done.
+    pub async fn from_password<P: Into<Password>>(
+        password: P,
+        inner: R,
+    ) -> Result<AsyncDecryptReader<R>> {
+        AsyncDecryptReader::from_password_with_params(password, inner, &KdfParams::default()).await
+    }
+
+    /// Like `from_password`, but derives the keys with the given `KdfParams`.
+    pub async fn from_password_with_params<P: Into<Password>>(
+        password: P,
+        mut inner: R,
+        params: &KdfParams,
+    ) -> Result<AsyncDecryptReader<R>> {
+        let mut header = [0; PASSWORD_HEADER_LEN];
+        let mut read = 0;
+        while read < PASSWORD_HEADER_LEN {
+            let polled =
+                future::poll_fn(|cx| Pin::new(&mut inner).poll_read(cx, &mut header[read..]));
+            match polled.await {
+                Ok(0) => break,
+                Ok(n) => read += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    return Err(Error::new(
+                        ErrorKind::IOError(e),
+                        "Reading the header failed.".to_owned(),
+                    ))
+                }
+            }
+        }
+        if read < PASSWORD_HEADER_LEN {
+            return Err(Error::new(
+                ErrorKind::NotEnoughInput(read),
+                "Decryption failed, not enough input.".to_owned(),
+            ));
+        }
+
+        let decryptor = Decryptor::from_header(&password.into(), Header::parse(&header)?, params)?;
+        Ok(AsyncDecryptReader::new(
+            DecryptionContext::from_decryptor(&decryptor)?,
+            inner,
+        ))
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for AsyncDecryptReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let AsyncDecryptReader { inner, state } = self.get_mut();
+        state.poll_read(buf, |chunk| Pin::new(&mut *inner).poll_read(cx, chunk))
+    }
+}
@@ -0,0 +1,233 @@
+use super::{
+    adapter::{Decrypting, Encrypting},
+    context::DecryptionContext,
+    decryptor::Decryptor,
+    encryptor::Encryptor,
+    errors::{Error, ErrorKind, Result},
+    header::{Header, PASSWORD_HEADER_LEN},
+    types::*,
+};
+use std::{
+    io,
+    pin::Pin,
+    task::{self, ready, Poll},
+};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, ReadBuf};
+
+/// Derives the `EncryptionKey` and the `HMACKey` out of a password on tokio's blocking thread
+/// pool, as PBKDF2 is (deliberately) slow enough to stall the executor otherwise.
+pub async fn derive_keys<P: Into<Password>>(
+    password: P,
+    encryption_salt: EncryptionSalt,
+    hmac_salt: HMACSalt,
+    params: &KdfParams,
+) -> Result<(EncryptionKey, HMACKey)> {
+    let password = password.into();
+    let params = *params;
+    offload(move || {
+        Ok((
+            EncryptionKey::new_with_params(&encryption_salt, password.as_bytes(), &params),
+            HMACKey::new_with_params(&hmac_salt, password.as_bytes(), &params),
+        ))
+    })
+    .await
+}
+
+/// Runs `derive` on the blocking thread pool, forwarding its panics.
+async fn offload<T, F>(derive: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    match tokio::task::spawn_blocking(derive).await {
+        Ok(result) => result,
+        Err(error) => match error.try_into_panic() {
+            Ok(panic) => std::panic::resume_unwind(panic),
+            Err(error) => Err(Error::new(
+                ErrorKind::IOError(io::Error::new(io::ErrorKind::Interrupted, error)),
+                "The key derivation was cancelled.".to_owned(),
+            )),
+        },
+    }
+}
+
+/// An `AsyncWrite` adapter which encrypts everything written into it, streaming the resulting
+/// RNCryptor message into the wrapped writer: the asynchronous `EncryptingWriter`.
+///
+/// The message is completed by `shutdown`, which writes the padded last block and the HMAC
+/// before shutting the wrapped writer down: dropping the writer without shutting it down
+/// leaves a truncated (and therefore invalid) message behind.
+pub struct AsyncEncryptWriter<W> {
+    inner: W,
+    state: Encrypting,
+}
+
+impl<W: AsyncWrite + Unpin> AsyncEncryptWriter<W> {
+    /// Wraps `inner`, encrypting with the given `Encryptor`.
+    pub fn new(encryptor: &Encryptor, inner: W) -> Result<AsyncEncryptWriter<W>> {
+        Ok(AsyncEncryptWriter {
+            inner,
+            state: Encrypting::new(encryptor.context()?),
+        })
+    }
+
+    /// Wraps `inner`, encrypting with keys derived from `password` (and random salts and `IV`)
+    /// on the blocking thread pool.
+    pub async fn from_password<P: Into<Password>>(
+        password: P,
+        inner: W,
+    ) -> Result<AsyncEncryptWriter<W>> {
+        AsyncEncryptWriter::from_password_with_params(password, inner, &KdfParams::default()).await
+    }
+
+    /// Like `from_password`, but derives the keys with the given `KdfParams`.
+    pub async fn from_password_with_params<P: Into<Password>>(
+        password: P,
+        inner: W,
+        params: &KdfParams,
+    ) -> Result<AsyncEncryptWriter<W>> {
+        let password = password.into();
+        let params = *params;
+        let encryptor = offload(move || {
+            Encryptor::from_password_with_params(
+                password,
+                Salt::new()?,
+                Salt::new()?,
+                IV::new()?,
+                &params,
+            )
+        })
+        .await?;
+        AsyncEncryptWriter::new(&encryptor, inner)
+    }
+
+    /// Gets a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for AsyncEncryptWriter<W> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let AsyncEncryptWriter { inner, state } = self.get_mut();
+        state.poll_write(buf, |output| Pin::new(&mut *inner).poll_write(cx, output))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        let AsyncEncryptWriter { inner, state } = self.get_mut();
+        ready!(state.poll_drain(|output| Pin::new(&mut *inner).poll_write(cx, output)))?;
+        Pin::new(inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        let AsyncEncryptWriter { inner, state } = self.get_mut();
+        ready!(state.poll_finish(|output| Pin::new(&mut *inner).poll_write(cx, output)))?;
+        Pin::new(inner).poll_shutdown(cx)
+    }
+}
+
+/// An `AsyncRead` adapter which decrypts a RNCryptor message while reading it: the
+/// asynchronous `DecryptingReader`.
+///
+/// As with the `DecryptingReader`, plain text is handed out **before** it has been
+/// authenticated: a failed validation is reported by the final read, and everything read until
+/// then must be discarded by the caller.
+pub struct AsyncDecryptReader<R> {
+    inner: R,
+    state: Decrypting,
+}
+
+impl<R: AsyncRead + Unpin> AsyncDecryptReader<R> {
+    /// Wraps `inner`, decrypting with the given `DecryptionContext`.
+    pub fn new(context: DecryptionContext, inner: R) -> AsyncDecryptReader<R> {
+        AsyncDecryptReader {
+            inner,
+            state: Decrypting::new(context),
+        }
+    }
+
+    /// Wraps `inner`, which holds a message produced by the key-based API.
+    pub fn with_keys(
+        encryption_key: EncryptionKey,
+        hmac_key: HMACKey,
+        inner: R,
+    ) -> AsyncDecryptReader<R> {
+        AsyncDecryptReader::new(
+            DecryptionContext::with_keys(encryption_key, hmac_key),
+            inner,
+        )
+    }
+
+    /// Reads the header out of `inner` and derives the keys from `password` on the blocking
+    /// thread pool.
+    pub async fn from_password<P: Into<Password>>(
+        password: P,
+        inner: R,
+    ) -> Result<AsyncDecryptReader<R>> {
+        AsyncDecryptReader::from_password_with_params(password, inner, &KdfParams::default()).await
+    }
+
+    /// Like `from_password`, but derives the keys with the given `KdfParams`.
+    pub async fn from_password_with_params<P: Into<Password>>(
+        password: P,
+        mut inner: R,
+        params: &KdfParams,
+    ) -> Result<AsyncDecryptReader<R>> {
+        let mut header = [0; PASSWORD_HEADER_LEN];
+        let mut read = 0;
+        while read < PASSWORD_HEADER_LEN {
+            match inner.read(&mut header[read..]).await {
+                Ok(0) => break,
+                Ok(n) => read += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    return Err(Error::new(
+                        ErrorKind::IOError(e),
+                        "Reading the header failed.".to_owned(),
+                    ))
+                }
+            }
+        }
+        if read < PASSWORD_HEADER_LEN {
+            return Err(Error::new(
+                ErrorKind::NotEnoughInput(read),
+                "Decryption failed, not enough input.".to_owned(),
+            ));
+        }
+
+        let header = Header::parse(&header)?;
+        let password = password.into();
+        let params = *params;
+        let decryptor = offload(move || Decryptor::from_header(&password, header, &params)).await?;
+        Ok(AsyncDecryptReader::new(
+            DecryptionContext::from_decryptor(&decryptor)?,
+            inner,
+        ))
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for AsyncDecryptReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let AsyncDecryptReader { inner, state } = self.get_mut();
+        let read = ready!(state.poll_read(buf.initialize_unfilled(), |chunk| {
+            let mut chunk = ReadBuf::new(chunk);
+            ready!(Pin::new(&mut *inner).poll_read(cx, &mut chunk))?;
+            Poll::Ready(Ok(chunk.filled().len()))
+        }))?;
+        buf.advance(read);
+        Poll::Ready(Ok(()))
+    }
+}
@@ -0,0 +1,108 @@
+use super::{
+    decryptor::Decryptor,
+    encryptor::ReusableEncryptor,
+    errors::{Error, ErrorKind, Result},
+    types::{EncryptionKey, HMACKey},
+};
+use bytes::{Bytes, BytesMut};
+use std::io;
+use tokio_util::codec::{length_delimited, Decoder, Encoder, LengthDelimitedCodec};
+
+/// The length of the big-endian length prefix of every frame, by default.
+pub const DEFAULT_LENGTH_FIELD_LEN: usize = 4;
+/// The largest frame (once encrypted) accepted by default.
+pub const DEFAULT_MAX_FRAME_LEN: usize = 8 * 1024 * 1024;
+
+/// A `tokio_util` codec turning a byte stream into a stream of length-prefixed, key-based
+/// RNCryptor messages: items are encrypted (each with a fresh random `IV`) when sent, and
+/// decrypted and authenticated when received. Wrap a transport in a `Framed` with it to get an
+/// encrypted message transport.
+///
+/// A frame which fails to decrypt is reported as an `io::Error` wrapping the RNCryptor `Error`;
+/// the frames around it are unaffected.
+pub struct MessageCodec {
+    encryptor: ReusableEncryptor,
+    encryption_key: EncryptionKey,
+    hmac_key: HMACKey,
+    framing: LengthDelimitedCodec,
+}
+
+impl MessageCodec {
+    /// A codec using the default framing: a 4-byte length prefix and frames of up to 8 MiB.
+    pub fn new(encryption_key: EncryptionKey, hmac_key: HMACKey) -> MessageCodec {
+        MessageCodec::framed(
+            encryption_key,
+            hmac_key,
+            DEFAULT_LENGTH_FIELD_LEN,
+            DEFAULT_MAX_FRAME_LEN,
+        )
+    }
+
+    /// A codec using a big-endian length prefix of `length_field_len` bytes and rejecting frames
+    /// longer than `max_frame_len` in either direction. The prefix must be between 1 and 8
+    /// bytes long, `ErrorKind::WrongInputSize` is returned otherwise.
+    pub fn with_framing(
+        encryption_key: EncryptionKey,
+        hmac_key: HMACKey,
+        length_field_len: usize,
+        max_frame_len: usize,
+    ) -> Result<MessageCodec> {
+        if !(1..=8).contains(&length_field_len) {
+            return Err(Error::new(
+                ErrorKind::WrongInputSize(length_field_len),
+                "The length prefix must be between 1 and 8 bytes long.".to_owned(),
+            ));
+        }
+
+        Ok(MessageCodec::framed(
+            encryption_key,
+            hmac_key,
+            length_field_len,
+            max_frame_len,
+        ))
+    }
+
+    /// Builds the codec, `length_field_len` having been validated already.
+    fn framed(
+        encryption_key: EncryptionKey,
+        hmac_key: HMACKey,
+        length_field_len: usize,
+        max_frame_len: usize,
+    ) -> MessageCodec {
+        let framing = length_delimited::Builder::new()
+            .length_field_length(length_field_len)
+            .max_frame_length(max_frame_len)
+            .new_codec();
+        MessageCodec {
+            encryptor: ReusableEncryptor::from_keys(encryption_key.clone(), hmac_key.clone()),
+            encryption_key,
+            hmac_key,
+            framing,
+        }
+    }
+}
+
+impl Encoder<Bytes> for MessageCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: Bytes, dst: &mut BytesMut) -> io::Result<()> {
+        let message = self.encryptor.encrypt(&item)?;
+        self.framing.encode(Bytes::from(message), dst)
+    }
+}
+
+impl Decoder for MessageCodec {
+    type Item = Bytes;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<Bytes>> {
+        let frame = match self.framing.decode(src)? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+        let plain_text =
+            Decryptor::from_keys(self.encryption_key.clone(), self.hmac_key.clone(), &frame)
+                .and_then(|decryptor| decryptor.decrypt(&frame))?;
+        Ok(Some(Bytes::from(plain_text)))
+    }
+}
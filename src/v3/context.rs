@@ -0,0 +1,350 @@
+use super::{
+    decryptor::{authentication_failed, unexpected_options, Decryptor},
+    errors::{Error, ErrorKind, Result},
+    header::{Header, VERSION},
+    types::*,
+};
+use aes::cipher::{
+    block_padding::{Padding, Pkcs7},
+    generic_array::GenericArray,
+    BlockDecryptMut, BlockEncryptMut, KeyIvInit,
+};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+type Aes256CbcEnc = cbc::Encryptor<aes::Aes256>;
+type HmacSha256 = Hmac<Sha256>;
+
+const BLOCK_SIZE: usize = 16;
+
+/// A push-style encryption context, mirroring the `update(withData:)`/`finalData()` API of the
+/// Swift implementation: feed it chunks of any size with `update`, then collect the rest of the
+/// message with `finalize`. The concatenation of everything returned is exactly what
+/// `Encryptor::encrypt` would have produced for the concatenated chunks.
+///
+/// Dropping a context without finalizing it leaves a truncated (and therefore invalid) message
+/// behind.
+pub struct EncryptionContext {
+    cipher: Aes256CbcEnc,
+    mac: HmacSha256,
+    header: Option<Vec<u8>>,
+    buffer: Zeroizing<Vec<u8>>,
+}
+
+impl EncryptionContext {
+    /// Takes the already serialized `header`, so that the context is oblivious to the version
+    /// of the format it writes.
+    pub(super) fn new(
+        encryption_key: &EncryptionKey,
+        hmac_key: &HMACKey,
+        header: Vec<u8>,
+        iv: &IV,
+    ) -> Result<EncryptionContext> {
+        let cipher = Aes256CbcEnc::new(encryption_key.as_slice().into(), iv.as_slice().into());
+        let mut mac = HmacSha256::new_from_slice(hmac_key.as_slice())
+            .map_err(|error| Error::new(ErrorKind::HMACGenerationFailed, error.to_string()))?;
+        mac.update(&header);
+
+        Ok(EncryptionContext {
+            cipher,
+            mac,
+            header: Some(header),
+            buffer: Zeroizing::new(Vec::with_capacity(BLOCK_SIZE)),
+        })
+    }
+
+    /// Encrypts `data`, returning the part of the message which is ready: the header on the
+    /// first call, then every complete AES block. Up to 15 bytes are held back until the next
+    /// call (or `finalize`).
+    pub fn update(&mut self, data: &[u8]) -> Vec<u8> {
+        let mut output = Vec::with_capacity(data.len() + BLOCK_SIZE);
+        self.update_into(data, &mut output);
+        output
+    }
+
+    /// Like `update`, but appends the output to a caller-provided buffer.
+    pub fn update_into(&mut self, data: &[u8], output: &mut Vec<u8>) {
+        self.emit_header(output);
+
+        let mut input = data;
+        if !self.buffer.is_empty() {
+            let needed = BLOCK_SIZE - self.buffer.len();
+            let taken = needed.min(input.len());
+            self.buffer.extend_from_slice(&input[..taken]);
+            input = &input[taken..];
+            if self.buffer.len() < BLOCK_SIZE {
+                return;
+            }
+            let mut block = std::mem::take(&mut self.buffer);
+            self.encrypt_blocks(&mut block, output);
+            block.clear();
+            self.buffer = block;
+        }
+
+        let aligned = input.len() - input.len() % BLOCK_SIZE;
+        if aligned > 0 {
+            let mut blocks = Zeroizing::new(input[..aligned].to_vec());
+            self.encrypt_blocks(&mut blocks, output);
+        }
+        self.buffer.extend_from_slice(&input[aligned..]);
+    }
+
+    /// Completes the message, returning the PKCS7-padded last block and the HMAC (preceded by
+    /// the header, if `update` was never called).
+    pub fn finalize(self) -> Vec<u8> {
+        let mut output = Vec::with_capacity(BLOCK_SIZE + HMAC_LEN);
+        self.finalize_into(&mut output);
+        output
+    }
+
+    /// Like `finalize`, but appends the output to a caller-provided buffer.
+    pub fn finalize_into(mut self, output: &mut Vec<u8>) {
+        self.emit_header(output);
+
+        let mut last = std::mem::take(&mut self.buffer);
+        let padding = BLOCK_SIZE - last.len();
+        last.resize(BLOCK_SIZE, padding as u8);
+        self.encrypt_blocks(&mut last, output);
+
+        output.extend_from_slice(&self.mac.finalize().into_bytes());
+    }
+
+    /// Outputs the header, unless it has been output already.
+    fn emit_header(&mut self, output: &mut Vec<u8>) {
+        if let Some(header) = self.header.take() {
+            output.extend(header);
+        }
+    }
+
+    /// Encrypts the given (block-aligned) bytes in place, feeds them to the HMAC and appends
+    /// them to `output`.
+    fn encrypt_blocks(&mut self, blocks: &mut [u8], output: &mut Vec<u8>) {
+        for block in blocks.chunks_exact_mut(BLOCK_SIZE) {
+            self.cipher
+                .encrypt_block_mut(GenericArray::from_mut_slice(block));
+        }
+        self.mac.update(blocks);
+        output.extend_from_slice(blocks);
+    }
+}
+
+type Aes256CbcDec = cbc::Decryptor<aes::Aes256>;
+
+/// How many trailing bytes are held back until the end of the message: the last cipher text
+/// block (which carries the padding) and the HMAC.
+const WITHHELD: usize = BLOCK_SIZE + HMAC_LEN;
+
+/// What a `DecryptionContext` produces for the bytes it's fed.
+#[derive(Debug)]
+pub enum Event {
+    /// Plain text decrypted out of the bytes fed so far (possibly none), **not yet
+    /// authenticated**: it must be thrown away unless the message ends up `Authenticated`.
+    PlainText(Zeroizing<Vec<u8>>),
+    /// The whole message has been authenticated; carries the last plain text bytes.
+    Authenticated(Zeroizing<Vec<u8>>),
+    /// The message is malformed or could not be authenticated.
+    Failed(Error),
+}
+
+/// Where the keys of a `DecryptionContext` come from, once the header is known.
+enum Credentials {
+    Password(Password, KdfParams),
+    Keys(EncryptionKey, HMACKey),
+}
+
+enum State {
+    Header(Credentials),
+    Body {
+        cipher: Box<Aes256CbcDec>,
+        mac: HmacSha256,
+    },
+    Failed,
+}
+
+/// A decryption core which does no I/O at all: `feed` it the bytes of a message as they come
+/// in, collect the `Event`s, and `finish` it once the message ended. It parses the header,
+/// decrypts every complete AES block but the last one, and withholds the trailing HMAC until
+/// `finish` validates it, which makes it suitable to wrap into any kind of adapter (`io::Read`,
+/// async, FFI...).
+pub struct DecryptionContext {
+    state: State,
+    pending: Vec<u8>,
+}
+
+impl DecryptionContext {
+    /// A context for a password-based message. The keys are derived as soon as the header has
+    /// been fed.
+    pub fn with_password<P: Into<Password>>(password: P) -> DecryptionContext {
+        DecryptionContext::with_password_and_params(password, &KdfParams::default())
+    }
+
+    /// Like `with_password`, but derives the keys with the given `KdfParams`.
+    pub fn with_password_and_params<P: Into<Password>>(
+        password: P,
+        params: &KdfParams,
+    ) -> DecryptionContext {
+        DecryptionContext::new(Credentials::Password(password.into(), *params))
+    }
+
+    /// A context for a message produced by the key-based API.
+    pub fn with_keys(encryption_key: EncryptionKey, hmac_key: HMACKey) -> DecryptionContext {
+        DecryptionContext::new(Credentials::Keys(encryption_key, hmac_key))
+    }
+
+    /// A context for a message whose header has already been dealt with by a `Decryptor`: only
+    /// the bytes following the header must be fed.
+    pub fn from_decryptor(decryptor: &Decryptor) -> Result<DecryptionContext> {
+        Ok(DecryptionContext {
+            state: body(decryptor)?,
+            pending: Vec::new(),
+        })
+    }
+
+    fn new(credentials: Credentials) -> DecryptionContext {
+        DecryptionContext {
+            state: State::Header(credentials),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Feeds the next bytes of the message, returning the plain text they completed (if any)
+    /// or the reason the message was rejected. A context which failed keeps failing.
+    pub fn feed(&mut self, data: &[u8]) -> Event {
+        match self.try_feed(data) {
+            Ok(plain_text) => Event::PlainText(plain_text),
+            Err(error) => self.fail(error),
+        }
+    }
+
+    /// Signals the end of the message: validates the HMAC and decrypts the last, padded,
+    /// block.
+    pub fn finish(mut self) -> Event {
+        match self.try_finish() {
+            Ok(plain_text) => Event::Authenticated(plain_text),
+            Err(error) => self.fail(error),
+        }
+    }
+
+    fn fail(&mut self, error: Error) -> Event {
+        self.state = State::Failed;
+        self.pending.clear();
+        Event::Failed(error)
+    }
+
+    fn try_feed(&mut self, data: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+        self.pending.extend_from_slice(data);
+        if let State::Header(_) = self.state {
+            if !self.parse_header()? {
+                return Ok(Zeroizing::default());
+            }
+        }
+
+        let (cipher, mac) = match self.state {
+            State::Body {
+                ref mut cipher,
+                ref mut mac,
+            } => (cipher, mac),
+            _ => return Err(previously_failed()),
+        };
+        let available = self.pending.len().saturating_sub(WITHHELD);
+        let aligned = available - available % BLOCK_SIZE;
+        let mut blocks = Zeroizing::new(self.pending.drain(..aligned).collect::<Vec<u8>>());
+        mac.update(&blocks);
+        for block in blocks.chunks_exact_mut(BLOCK_SIZE) {
+            cipher.decrypt_block_mut(GenericArray::from_mut_slice(block));
+        }
+        Ok(blocks)
+    }
+
+    /// Parses the header out of the pending bytes and sets up the keys, returning whether
+    /// enough bytes came in to do so.
+    fn parse_header(&mut self) -> Result<bool> {
+        if self.pending.len() < 2 {
+            return Ok(false);
+        }
+        let header = match Header::parse(&self.pending) {
+            Err(Error {
+                kind: ErrorKind::TruncatedHeader(_),
+                ..
+            }) => return Ok(false),
+            header => header?,
+        };
+
+        let decryptor = match std::mem::replace(&mut self.state, State::Failed) {
+            State::Header(Credentials::Password(password, params)) => {
+                Decryptor::from_header(&password, header, &params)?
+            }
+            State::Header(Credentials::Keys(encryption_key, hmac_key)) => {
+                if let Header::Password { .. } = header {
+                    return Err(unexpected_options(&header));
+                }
+                Decryptor::new(VERSION, header, encryption_key, hmac_key)
+            }
+            _ => return Err(previously_failed()),
+        };
+        self.pending.drain(..decryptor.header().encoded_len());
+        self.state = body(&decryptor)?;
+        Ok(true)
+    }
+
+    fn try_finish(&mut self) -> Result<Zeroizing<Vec<u8>>> {
+        let (mut cipher, mut mac) = match std::mem::replace(&mut self.state, State::Failed) {
+            State::Body { cipher, mac } => (cipher, mac),
+            State::Header(_) => {
+                return Err(Error::new(
+                    ErrorKind::NotEnoughInput(self.pending.len()),
+                    "Decryption failed, not enough input.".to_owned(),
+                ))
+            }
+            State::Failed => return Err(previously_failed()),
+        };
+        if self.pending.len() < WITHHELD {
+            return Err(Error::new(
+                ErrorKind::NotEnoughInput(self.pending.len()),
+                "Decryption failed, not enough input.".to_owned(),
+            ));
+        }
+        if self.pending.len() != WITHHELD {
+            return Err(Error::new(
+                ErrorKind::WrongInputSize(self.pending.len() - HMAC_LEN),
+                "The cipher text is not block-aligned.".to_owned(),
+            ));
+        }
+
+        let hmac = HMAC::from_slice(&self.pending.split_off(BLOCK_SIZE))?;
+        let mut last = Zeroizing::new(std::mem::take(&mut self.pending));
+        mac.update(&last);
+        let computed_hmac = HMAC(mac.finalize().into_bytes().into());
+        if !hmac.is_equal_in_consistent_time_to(&computed_hmac) {
+            return Err(authentication_failed());
+        }
+
+        let block = GenericArray::from_mut_slice(&mut last);
+        cipher.decrypt_block_mut(block);
+        let unpadded = Pkcs7::unpad(block).map_err(|_| authentication_failed())?;
+        Ok(Zeroizing::new(unpadded.to_vec()))
+    }
+}
+
+/// The state decrypting the body of the message the `decryptor` was built for.
+fn body(decryptor: &Decryptor) -> Result<State> {
+    let cipher = Aes256CbcDec::new(
+        decryptor.encryption_key.as_slice().into(),
+        decryptor.iv.as_slice().into(),
+    );
+    let mut mac = HmacSha256::new_from_slice(decryptor.hmac_key.as_slice())
+        .map_err(|error| Error::new(ErrorKind::HMACGenerationFailed, error.to_string()))?;
+    mac.update(&decryptor.header().to_bytes_version(decryptor.version));
+    Ok(State::Body {
+        cipher: Box::new(cipher),
+        mac,
+    })
+}
+
+fn previously_failed() -> Error {
+    Error::new(
+        ErrorKind::AuthenticationFailed,
+        "Decryption previously failed.".to_owned(),
+    )
+}
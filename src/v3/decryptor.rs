@@ -1,25 +1,44 @@
+#[cfg(feature = "parallel")]
+use super::parallel;
 use super::{
     errors::{Error, ErrorKind, Result},
+    header::{Header, VERSION},
     types::*,
 };
 use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, KeyIvInit};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::io::{self, Read};
+use zeroize::Zeroizing;
 
 type Aes256CbcDec = cbc::Decryptor<aes::Aes256>;
+type HmacSha256 = Hmac<Sha256>;
+
+const CHUNK_SIZE: usize = 8 * 1024;
 
 /// A "Decryptor", which is nothing more than a data structure to keep around the RNCryptor context
+#[derive(Debug)]
 pub struct Decryptor {
     pub version: u8,
     pub options: u8,
-    encryption_salt: EncryptionSalt,
-    hmac_salt: HMACSalt,
-    encryption_key: EncryptionKey,
-    pub hmac_key: HMACKey,
-    iv: IV,
+    header: Header,
+    pub(super) encryption_key: EncryptionKey,
+    pub(super) hmac_key: HMACKey,
+    pub(super) iv: IV,
 }
 
 impl Decryptor {
     /// Builds a "Decryptor" out of a password and a message (to decrypt).
-    pub fn from(password: &str, message: &[u8]) -> Result<Decryptor> {
+    pub fn from<P: Into<Password>>(password: P, message: &[u8]) -> Result<Decryptor> {
+        Decryptor::from_with_params(password, message, &KdfParams::default())
+    }
+
+    /// Like `from`, but derives the keys with the given `KdfParams`.
+    pub fn from_with_params<P: Into<Password>>(
+        password: P,
+        message: &[u8],
+        params: &KdfParams,
+    ) -> Result<Decryptor> {
         let msg_len = message.len();
         if msg_len < 66 {
             return Err(Error::new(
@@ -28,62 +47,228 @@ impl Decryptor {
             ));
         }
 
-        let version = message[0];
-        let options = message[1];
-        let encryption_salt = Salt(message[2..10].to_vec());
-        let hmac_salt = Salt(message[10..18].to_vec());
-        let iv = IV::from(message[18..34].to_vec());
-        let encryption_key = EncryptionKey::new(&encryption_salt, password.as_bytes());
-        let hmac_key = HMACKey::new(&hmac_salt, password.as_bytes());
+        Decryptor::from_header(&password.into(), Header::parse(message)?, params)
+    }
+
+    /// Builds a "Decryptor" out of a password and an already parsed password-based `Header`.
+    pub(super) fn from_header(
+        password: &Password,
+        header: Header,
+        params: &KdfParams,
+    ) -> Result<Decryptor> {
+        let (encryption_key, hmac_key) = match header {
+            Header::Password {
+                ref encryption_salt,
+                ref hmac_salt,
+                ..
+            } => (
+                EncryptionKey::new_with_params(encryption_salt, password.as_bytes(), params),
+                HMACKey::new_with_params(hmac_salt, password.as_bytes(), params),
+            ),
+            Header::Key { .. } => return Err(unexpected_options(&header)),
+        };
+
+        Ok(Decryptor::new(VERSION, header, encryption_key, hmac_key))
+    }
 
-        Ok(Decryptor {
+    /// Builds a "Decryptor" out of an `EncryptionKey`, an `HMACKey` and a message (to decrypt)
+    /// encrypted with the key-based API.
+    pub fn from_keys(
+        encryption_key: EncryptionKey,
+        hmac_key: HMACKey,
+        message: &[u8],
+    ) -> Result<Decryptor> {
+        let msg_len = message.len();
+        if msg_len < 66 {
+            return Err(Error::new(
+                ErrorKind::NotEnoughInput(msg_len),
+                "Decryption failed, not enough input.".to_owned(),
+            ));
+        }
+
+        let header = Header::parse(message)?;
+        if let Header::Password { .. } = header {
+            return Err(unexpected_options(&header));
+        }
+
+        Ok(Decryptor::new(VERSION, header, encryption_key, hmac_key))
+    }
+
+    /// Builds a "Decryptor" for any version sharing the v3 layout, which authenticates messages
+    /// against `header` serialized with the given `version` byte.
+    pub(crate) fn new(
+        version: u8,
+        header: Header,
+        encryption_key: EncryptionKey,
+        hmac_key: HMACKey,
+    ) -> Decryptor {
+        Decryptor {
             version,
-            options,
-            encryption_salt,
+            options: header.options(),
+            iv: header.iv().clone(),
+            header,
             encryption_key,
             hmac_key,
-            hmac_salt,
-            iv,
-        })
+        }
+    }
+
+    /// Returns the `Header` this "Decryptor" authenticates messages against.
+    pub(super) fn header(&self) -> &Header {
+        &self.header
     }
 
     fn plain_text(&self, cipher_text: &[u8]) -> Result<Message> {
-        let iv = self.iv.to_vec();
-        let key = self.encryption_key.to_vec();
+        let decryptor = Aes256CbcDec::new(
+            self.encryption_key.as_slice().into(),
+            self.iv.as_slice().into(),
+        );
 
-        let decryptor = Aes256CbcDec::new(key.as_slice().into(), iv.as_slice().into());
-        let decrypted = decryptor
-            .decrypt_padded_vec_mut::<Pkcs7>(cipher_text)
-            .map_err(|error| Error::new(ErrorKind::UnpadError, error.to_string()))?;
+        // Decrypt in a buffer we own, so that it gets wiped should the unpadding fail.
+        let mut decrypted = Zeroizing::new(cipher_text.to_vec());
+        let len = decryptor
+            .decrypt_padded_mut::<Pkcs7>(&mut decrypted)
+            .map_err(|error| Error::new(ErrorKind::UnpadError, error.to_string()))?
+            .len();
+        decrypted.truncate(len);
 
-        Ok(decrypted)
+        Ok(std::mem::take(&mut *decrypted))
     }
 
     /// Decrypts a `cipher_text`, returning a `Message` or an `Error`.
+    ///
+    /// The HMAC is validated before anything gets decrypted, and every failure to authenticate
+    /// or unpad the message is reported as the same opaque `ErrorKind::AuthenticationFailed`,
+    /// so that callers decrypting untrusted input don't turn into a padding oracle.
     pub fn decrypt(&self, cipher_text: &[u8]) -> Result<Message> {
-        let mut header: Vec<u8> = vec![3, 1];
-        header.extend(self.encryption_salt.as_slice().iter());
-        header.extend(self.hmac_salt.as_slice().iter());
-        header.extend(self.iv.as_slice().iter());
+        self.decrypt_with_diagnostics(cipher_text)
+            .map_err(|error| match error.kind {
+                ErrorKind::HMACValidationFailed | ErrorKind::UnpadError => authentication_failed(),
+                _ => error,
+            })
+    }
 
-        //TODO: Do not depend from drain, as this is O(n).
-        let mut cipher_text_vec = Vec::from(&cipher_text[34..]);
-        let hmac_position = cipher_text_vec.len() - 32;
-        let hmac0 = cipher_text_vec.drain(hmac_position..).collect();
+    /// Like `decrypt`, but reports the detailed cause of a failure (`HMACValidationFailed`
+    /// vs. `UnpadError`) instead of `AuthenticationFailed`.
+    ///
+    /// **Note: This is meant for debugging only: never let the detailed error reach whoever
+    /// supplied the message.**
+    pub fn decrypt_with_diagnostics(&self, cipher_text: &[u8]) -> Result<Message> {
+        let (encrypted, hmac) = self.split(cipher_text)?;
+        let header = self.header.to_bytes_version(self.version);
 
-        let encrypted = cipher_text_vec.as_slice();
+        let computed_hmac = HMAC::over(&header, encrypted, &self.hmac_key)?;
+        validate(&hmac, &computed_hmac)?;
 
-        let message = self.plain_text(encrypted)?;
+        // Only authenticated messages get this far: large ones are then decrypted on the rayon
+        // thread pool.
+        #[cfg(feature = "parallel")]
+        if encrypted.len() >= parallel::MIN_LEN {
+            let mut plain_text = parallel::plain_text(&self.encryption_key, &self.iv, encrypted)?;
+            return Ok(std::mem::take(&mut *plain_text));
+        }
+
+        self.plain_text(encrypted)
+    }
+
+    /// Checks that `message` is authentic by validating its HMAC, without decrypting anything.
+    /// As with `decrypt`, a mismatch is reported as `ErrorKind::AuthenticationFailed`.
+    pub fn verify(&self, message: &[u8]) -> Result<()> {
+        let (encrypted, hmac) = self.split(message)?;
+        // As with `verify_reader`, the message must carry the header this "Decryptor" was built
+        // with: `decrypt` would reject it otherwise.
+        let header = self.header.to_bytes_version(self.version);
+        if message[..header.len()] != header[..] {
+            return Err(authentication_failed());
+        }
+
+        let computed_hmac = HMAC::over(&header, encrypted, &self.hmac_key)?;
+        validate(&hmac, &computed_hmac).map_err(|_| authentication_failed())
+    }
+
+    /// Like `verify`, but streams the message out of `reader`, which must be positioned at its
+    /// very beginning, so that it never has to fit in memory.
+    pub fn verify_reader<R: Read>(&self, mut reader: R) -> Result<()> {
+        let header = self.header.to_bytes_version(self.version);
+        let mut actual_header = vec![0; header.len()];
+        reader.read_exact(&mut actual_header).map_err(|e| {
+            Error::new(
+                ErrorKind::IOError(e),
+                "Reading the header failed.".to_owned(),
+            )
+        })?;
+        if actual_header != header {
+            return Err(authentication_failed());
+        }
 
-        let hmac = HMAC(hmac0);
-        let computed_hmac = HMAC::new(&Header(header), cipher_text_vec.as_slice(), &self.hmac_key)?;
+        let mut mac = <HmacSha256 as Mac>::new_from_slice(self.hmac_key.as_slice())
+            .map_err(|error| Error::new(ErrorKind::HMACGenerationFailed, error.to_string()))?;
+        mac.update(&header);
 
-        match hmac.is_equal_in_consistent_time_to(&computed_hmac) {
-            true => Ok(message),
-            false => Err(Error::new(
-                ErrorKind::HMACValidationFailed,
-                "HMAC mismatch.".to_owned(),
-            )),
+        // The trailing HMAC is only known once the reader is exhausted.
+        let mut pending = Vec::with_capacity(CHUNK_SIZE + HMAC_LEN);
+        let mut chunk = vec![0; CHUNK_SIZE];
+        loop {
+            let read = match reader.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    return Err(Error::new(
+                        ErrorKind::IOError(e),
+                        "Reading the cipher text failed.".to_owned(),
+                    ))
+                }
+            };
+            pending.extend_from_slice(&chunk[..read]);
+            let authenticated = pending.len().saturating_sub(HMAC_LEN);
+            mac.update(&pending[..authenticated]);
+            pending.drain(..authenticated);
         }
+
+        let hmac = HMAC::from_slice(&pending)?;
+        let computed_hmac = HMAC(mac.finalize().into_bytes().into());
+        validate(&hmac, &computed_hmac).map_err(|_| authentication_failed())
     }
+
+    /// Splits a message into its cipher text and its `HMAC`.
+    fn split<'a>(&self, message: &'a [u8]) -> Result<(&'a [u8], HMAC)> {
+        let header_len = self.header.encoded_len();
+        if message.len() < header_len + HMAC_LEN {
+            return Err(Error::new(
+                ErrorKind::HMACNotFound,
+                "Decryption failed, the HMAC is missing or truncated.".to_owned(),
+            ));
+        }
+
+        let hmac_position = message.len() - HMAC_LEN;
+        Ok((
+            &message[header_len..hmac_position],
+            HMAC::from_slice(&message[hmac_position..])?,
+        ))
+    }
+}
+
+fn validate(hmac: &HMAC, computed_hmac: &HMAC) -> Result<()> {
+    if !hmac.is_equal_in_consistent_time_to(computed_hmac) {
+        return Err(Error::new(
+            ErrorKind::HMACValidationFailed,
+            "HMAC mismatch.".to_owned(),
+        ));
+    }
+    Ok(())
+}
+
+/// The only error surfaced when a message cannot be authenticated or decrypted.
+pub(crate) fn authentication_failed() -> Error {
+    Error::new(
+        ErrorKind::AuthenticationFailed,
+        "Decryption failed, the message could not be authenticated.".to_owned(),
+    )
+}
+
+pub(crate) fn unexpected_options(header: &Header) -> Error {
+    Error::new(
+        ErrorKind::UnexpectedOptions(header.options()),
+        "The message was encrypted with a different API (password vs. keys).".to_owned(),
+    )
 }
@@ -1,16 +1,25 @@
 use super::{
+    context::EncryptionContext,
     errors::{Error, ErrorKind, Result},
+    header::{Header, VERSION},
     types::*,
+    writer::EncryptingWriter,
 };
 use aes::cipher::{
     block_padding::{NoPadding, Pkcs7},
     BlockEncryptMut, KeyIvInit,
 };
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+use std::io::Write;
 
 type Aes256CbcEnc = cbc::Encryptor<aes::Aes256>;
 
-#[derive(Clone)]
+/// An "Encryptor" is bound to the `IV` (and salts) it was built with, so it must only ever
+/// encrypt a **single** message: encrypting more would reuse them. Use a `ReusableEncryptor`
+/// to encrypt several messages with the same keys or password.
+#[derive(Clone, Debug)]
 pub struct Encryptor {
+    version: u8,
     encryption_key: EncryptionKey,
     hmac_key: HMACKey,
     header: Header,
@@ -18,12 +27,38 @@ pub struct Encryptor {
 }
 
 impl Encryptor {
-    pub fn from_password(
-        password: &str,
+    pub fn from_password<P: Into<Password>>(
+        password: P,
         es: EncryptionSalt,
         hs: HMACSalt,
         iv: IV,
     ) -> Result<Encryptor> {
+        Encryptor::from_password_with_params(password, es, hs, iv, &KdfParams::default())
+    }
+
+    /// Like `from_password`, but draws fresh salts and a fresh `IV` out of the given
+    /// cryptographically secure `rng`.
+    pub fn from_password_with_rng<R: CryptoRng + RngCore, P: Into<Password>>(
+        rng: &mut R,
+        password: P,
+    ) -> Result<Encryptor> {
+        let es = Salt::new_with_rng(rng)?;
+        let hs = Salt::new_with_rng(rng)?;
+        let iv = IV::new_with_rng(rng)?;
+        Encryptor::from_password(password, es, hs, iv)
+    }
+
+    /// Like `from_password`, but derives the keys with the given `KdfParams`. Note that
+    /// non-standard parameters aren't recorded in the message: the decrypting side must be told
+    /// about them out of band.
+    pub fn from_password_with_params<P: Into<Password>>(
+        password: P,
+        es: EncryptionSalt,
+        hs: HMACSalt,
+        iv: IV,
+        params: &KdfParams,
+    ) -> Result<Encryptor> {
+        let password = password.into();
         if password.is_empty() {
             return Err(Error::new(
                 ErrorKind::WrongInputSize(0),
@@ -31,47 +66,75 @@ impl Encryptor {
             ));
         }
 
-        let mut header: Vec<u8> = vec![3, 1];
-        header.extend(es.as_slice().iter());
-        header.extend(hs.as_slice().iter());
-        header.extend(iv.as_slice().iter());
+        Ok(Encryptor::new(
+            VERSION,
+            Header::Password {
+                encryption_salt: es.clone(),
+                hmac_salt: hs.clone(),
+                iv,
+            },
+            EncryptionKey::new_with_params(&es, password.as_bytes(), params),
+            HMACKey::new_with_params(&hs, password.as_bytes(), params),
+        ))
+    }
 
-        Ok(Encryptor {
-            encryption_key: EncryptionKey::new(&es, password.as_bytes()),
-            hmac_key: HMACKey::new(&hs, password.as_bytes()),
-            header: Header(header),
-            iv,
-        })
+    /// Builds an "Encryptor" out of the keys and a given `IV`. **Meant for test vectors and
+    /// for reproducing known messages**: anything else should rather use a
+    /// `ReusableEncryptor`, which draws a fresh `IV` for every message.
+    pub fn from_keys_with_fixed_iv(ek: EncryptionKey, hk: HMACKey, iv: IV) -> Result<Encryptor> {
+        Ok(Encryptor::new(VERSION, Header::Key { iv }, ek, hk))
     }
 
+    #[deprecated(
+        note = "use `ReusableEncryptor::from_keys`, or `from_keys_with_fixed_iv` for test vectors"
+    )]
     pub fn from_keys(ek: EncryptionKey, hk: HMACKey, iv: IV) -> Result<Encryptor> {
-        let mut header: Vec<u8> = vec![3, 0];
-        header.extend(iv.as_slice().iter());
-
-        Ok(Encryptor {
-            encryption_key: ek,
-            hmac_key: hk,
-            header: Header(header),
-            iv,
-        })
+        Encryptor::from_keys_with_fixed_iv(ek, hk, iv)
+    }
+
+    /// Builds an `Encryptor` writing messages in the given version of the format, for the
+    /// older formats sharing the v3 layout (i.e. v2).
+    pub(crate) fn new(
+        version: u8,
+        header: Header,
+        encryption_key: EncryptionKey,
+        hmac_key: HMACKey,
+    ) -> Encryptor {
+        Encryptor {
+            version,
+            encryption_key,
+            hmac_key,
+            iv: header.iv().clone(),
+            header,
+        }
     }
 
+    fn cipher(&self) -> Aes256CbcEnc {
+        Aes256CbcEnc::new(
+            self.encryption_key.as_slice().into(),
+            self.iv.as_slice().into(),
+        )
+    }
+
+    /// Encrypts a `plain_text` without padding it, hence its length must be a multiple of
+    /// the AES block size (16 bytes).
     pub fn cipher_text(&self, plain_text: &PlainText) -> Result<CipherText> {
-        let iv = self.iv.to_vec();
-        let key = self.encryption_key.to_vec();
+        if plain_text.len() % 16 != 0 {
+            return Err(Error::new(
+                ErrorKind::WrongInputSize(plain_text.len()),
+                "Unpadded plain text must be a multiple of 16 bytes.".to_owned(),
+            ));
+        }
 
-        let encryptor = Aes256CbcEnc::new(key.as_slice().into(), iv.as_slice().into());
-        let encrypted = encryptor.encrypt_padded_vec_mut::<NoPadding>(plain_text);
+        let encrypted = self
+            .cipher()
+            .encrypt_padded_vec_mut::<NoPadding>(plain_text);
 
         Ok(CipherText(encrypted))
     }
 
     pub fn cipher_text_pkcs7(&self, plain_text: &PlainText) -> Result<CipherText> {
-        let iv = self.iv.to_vec();
-        let key = self.encryption_key.to_vec();
-
-        let encryptor = Aes256CbcEnc::new(key.as_slice().into(), iv.as_slice().into());
-        let encrypted = encryptor.encrypt_padded_vec_mut::<Pkcs7>(plain_text);
+        let encrypted = self.cipher().encrypt_padded_vec_mut::<Pkcs7>(plain_text);
 
         Ok(CipherText(encrypted))
     }
@@ -85,15 +148,236 @@ impl Encryptor {
 
         let CipherText(ref text) = cipher_text;
 
-        let HMAC(hmac) = HMAC::new(&self.header, text.as_slice(), &self.hmac_key)?;
+        let header = self.header.to_bytes_version(self.version);
+        let HMAC(hmac) = HMAC::over(&header, text.as_slice(), &self.hmac_key)?;
 
         let mut message = Vec::new();
 
-        let Header(ref header) = self.header;
-        message.extend(header.as_slice());
+        message.extend(header);
         message.extend(text);
         message.extend(hmac.as_slice());
 
         Ok(message)
     }
+
+    /// Starts a push-style `EncryptionContext`. The concatenation of everything it outputs is
+    /// identical to what `encrypt` would return for the same input.
+    pub fn context(&self) -> Result<EncryptionContext> {
+        EncryptionContext::new(
+            &self.encryption_key,
+            &self.hmac_key,
+            self.header.to_bytes_version(self.version),
+            &self.iv,
+        )
+    }
+
+    /// Wraps `inner` into an `EncryptingWriter`, which streams the encrypted message into it.
+    /// The produced bytes are identical to the ones `encrypt` would return for the same input.
+    pub fn writer<W: Write>(&self, inner: W) -> Result<EncryptingWriter<W>> {
+        EncryptingWriter::new(
+            &self.encryption_key,
+            &self.hmac_key,
+            self.header.to_bytes_version(self.version),
+            &self.iv,
+            inner,
+        )
+    }
+}
+
+/// Where the keys of a `ReusableEncryptor` come from.
+#[derive(Clone, Debug)]
+enum Keys {
+    /// Keys supplied directly, with the key-based header.
+    Supplied {
+        encryption_key: EncryptionKey,
+        hmac_key: HMACKey,
+    },
+    /// Keys derived once out of a password and fixed salts.
+    Derived {
+        encryption_salt: EncryptionSalt,
+        hmac_salt: HMACSalt,
+        encryption_key: EncryptionKey,
+        hmac_key: HMACKey,
+    },
+    /// Keys derived again out of a password and fresh salts for every message.
+    Rederived {
+        password: Password,
+        params: KdfParams,
+    },
+}
+
+/// An encryptor meant to be built once (say, at startup) and to encrypt any number of messages,
+/// drawing a fresh random `IV` (and, if so configured, fresh salts) for every one of them. It's
+/// `Send + Sync`, so that it can be shared behind an `Arc`.
+#[derive(Clone, Debug)]
+pub struct ReusableEncryptor {
+    keys: Keys,
+}
+
+impl ReusableEncryptor {
+    /// Encrypts with the given keys, producing key-based messages.
+    pub fn from_keys(encryption_key: EncryptionKey, hmac_key: HMACKey) -> ReusableEncryptor {
+        ReusableEncryptor {
+            keys: Keys::Supplied {
+                encryption_key,
+                hmac_key,
+            },
+        }
+    }
+
+    /// Encrypts with keys derived out of `password` and fresh salts for every message, at the
+    /// cost of running the (slow) key derivation every time.
+    pub fn from_password<P: Into<Password>>(password: P) -> Result<ReusableEncryptor> {
+        ReusableEncryptor::from_password_with_params(password, &KdfParams::default())
+    }
+
+    /// Like `from_password`, but derives the keys with the given `KdfParams`.
+    pub fn from_password_with_params<P: Into<Password>>(
+        password: P,
+        params: &KdfParams,
+    ) -> Result<ReusableEncryptor> {
+        let password = password.into();
+        if password.is_empty() {
+            return Err(Error::new(
+                ErrorKind::WrongInputSize(0),
+                "Password length cannot be empty.".to_owned(),
+            ));
+        }
+
+        Ok(ReusableEncryptor {
+            keys: Keys::Rederived {
+                password,
+                params: *params,
+            },
+        })
+    }
+
+    /// Encrypts with keys derived once out of `password` and the given salts, which all the
+    /// messages share: only their `IV` changes.
+    pub fn from_password_with_fixed_salts<P: Into<Password>>(
+        password: P,
+        es: EncryptionSalt,
+        hs: HMACSalt,
+    ) -> Result<ReusableEncryptor> {
+        ReusableEncryptor::from_password_with_fixed_salts_and_params(
+            password,
+            es,
+            hs,
+            &KdfParams::default(),
+        )
+    }
+
+    /// Like `from_password_with_fixed_salts`, but derives the keys with the given `KdfParams`.
+    pub fn from_password_with_fixed_salts_and_params<P: Into<Password>>(
+        password: P,
+        es: EncryptionSalt,
+        hs: HMACSalt,
+        params: &KdfParams,
+    ) -> Result<ReusableEncryptor> {
+        let password = password.into();
+        if password.is_empty() {
+            return Err(Error::new(
+                ErrorKind::WrongInputSize(0),
+                "Password length cannot be empty.".to_owned(),
+            ));
+        }
+
+        Ok(ReusableEncryptor {
+            keys: Keys::Derived {
+                encryption_key: EncryptionKey::new_with_params(&es, password.as_bytes(), params),
+                hmac_key: HMACKey::new_with_params(&hs, password.as_bytes(), params),
+                encryption_salt: es,
+                hmac_salt: hs,
+            },
+        })
+    }
+
+    /// Builds the single-use `Encryptor` of the next message.
+    pub fn next_encryptor(&self) -> Result<Encryptor> {
+        self.next_encryptor_with_rng(&mut OsRng)
+    }
+
+    /// Like `next_encryptor`, but draws the `IV` (and salts) out of the given cryptographically
+    /// secure `rng`.
+    pub fn next_encryptor_with_rng<R: CryptoRng + RngCore>(
+        &self,
+        rng: &mut R,
+    ) -> Result<Encryptor> {
+        let iv = IV::new_with_rng(rng)?;
+        match self.keys {
+            Keys::Supplied {
+                ref encryption_key,
+                ref hmac_key,
+            } => Encryptor::from_keys_with_fixed_iv(encryption_key.clone(), hmac_key.clone(), iv),
+            Keys::Derived {
+                ref encryption_salt,
+                ref hmac_salt,
+                ref encryption_key,
+                ref hmac_key,
+            } => Ok(Encryptor::new(
+                VERSION,
+                Header::Password {
+                    encryption_salt: encryption_salt.clone(),
+                    hmac_salt: hmac_salt.clone(),
+                    iv,
+                },
+                encryption_key.clone(),
+                hmac_key.clone(),
+            )),
+            Keys::Rederived {
+                ref password,
+                ref params,
+            } => Encryptor::from_password_with_params(
+                password.clone(),
+                Salt::new_with_rng(rng)?,
+                Salt::new_with_rng(rng)?,
+                iv,
+                params,
+            ),
+        }
+    }
+
+    /// Encrypts a `plain_text` into a complete `Message`, with a fresh `IV`.
+    pub fn encrypt(&self, plain_text: &PlainText) -> Result<Message> {
+        self.next_encryptor()?.encrypt(plain_text)
+    }
+
+    /// Like `encrypt`, but draws the `IV` (and salts) out of the given cryptographically secure
+    /// `rng`.
+    pub fn encrypt_with_rng<R: CryptoRng + RngCore>(
+        &self,
+        rng: &mut R,
+        plain_text: &PlainText,
+    ) -> Result<Message> {
+        self.next_encryptor_with_rng(rng)?.encrypt(plain_text)
+    }
+
+    /// Starts a push-style `EncryptionContext` for the next message.
+    pub fn context(&self) -> Result<EncryptionContext> {
+        self.next_encryptor()?.context()
+    }
+
+    /// Like `context`, but draws the `IV` (and salts) out of the given cryptographically secure
+    /// `rng`.
+    pub fn context_with_rng<R: CryptoRng + RngCore>(
+        &self,
+        rng: &mut R,
+    ) -> Result<EncryptionContext> {
+        self.next_encryptor_with_rng(rng)?.context()
+    }
+
+    /// Wraps `inner` into an `EncryptingWriter` streaming the next message into it.
+    pub fn writer<W: Write>(&self, inner: W) -> Result<EncryptingWriter<W>> {
+        self.next_encryptor()?.writer(inner)
+    }
+
+    /// Like `writer`, but draws the `IV` (and salts) out of the given cryptographically secure
+    /// `rng`.
+    pub fn writer_with_rng<R: CryptoRng + RngCore, W: Write>(
+        &self,
+        rng: &mut R,
+        inner: W,
+    ) -> Result<EncryptingWriter<W>> {
+        self.next_encryptor_with_rng(rng)?.writer(inner)
+    }
 }
@@ -1,5 +1,9 @@
 use std;
-use std::result::Result as StdResult;
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    io,
+    result::Result as StdResult,
+};
 
 pub type Result<T> = StdResult<T, Error>;
 
@@ -10,10 +14,15 @@ pub enum ErrorKind {
     HMACGenerationFailed,
     /// The final check between the embedded HMAC and the computed one failed.
     HMACValidationFailed,
+    /// The message could not be authenticated or decrypted. This deliberately doesn't tell
+    /// a bad HMAC apart from a bad padding, see `Decryptor::decrypt_with_diagnostics`.
+    AuthenticationFailed,
     /// The HMAC wasn't found inside the encrypted packed during decryption.
     HMACNotFound,
     /// The input size was wrong.
     WrongInputSize(usize),
+    /// The input wasn't a valid hex string.
+    InvalidHex,
     /// Not enough input for decryption.
     NotEnoughInput(usize),
     /// The IV generation failed.
@@ -22,6 +31,17 @@ pub enum ErrorKind {
     SaltGenerationFailed(std::io::Error),
     /// The decryption failed due to invalid padding.
     UnpadError,
+    /// Reading from or writing to the underlying stream failed.
+    IOError(io::Error),
+    /// The version byte of the message isn't supported.
+    UnsupportedVersion(u8),
+    /// The options byte of the message has unknown bits set.
+    UnknownOptions(u8),
+    /// The options byte of the message doesn't match the decryption API used (e.g. a key-based
+    /// message handed to the password-based API).
+    UnexpectedOptions(u8),
+    /// The message ended before the header was complete.
+    TruncatedHeader(usize),
 }
 
 #[derive(Debug)]
@@ -47,3 +67,20 @@ impl Error {
         }
     }
 }
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{} ({:?})", self.message, self.kind)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<Error> for io::Error {
+    fn from(e: Error) -> io::Error {
+        match e.kind {
+            ErrorKind::IOError(inner) => inner,
+            _ => io::Error::new(io::ErrorKind::InvalidData, e),
+        }
+    }
+}
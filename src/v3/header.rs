@@ -0,0 +1,132 @@
+use super::{
+    errors::{Error, ErrorKind, Result},
+    types::*,
+};
+
+/// The version byte of every v3 message.
+pub const VERSION: u8 = 3;
+/// The options bit set when the keys are derived from a password.
+pub const OPTION_USES_PASSWORD: u8 = 1;
+
+/// The length of a password-based header: version, options, both salts and the IV.
+pub const PASSWORD_HEADER_LEN: usize = 34;
+/// The length of a key-based header: version, options and the IV.
+pub const KEY_HEADER_LEN: usize = 18;
+
+/// A RNCryptor v3 `Header`, which comes in two flavours depending on whether the keys were
+/// derived from a password or supplied directly.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Header {
+    /// The header of a message encrypted with the password-based API.
+    Password {
+        encryption_salt: EncryptionSalt,
+        hmac_salt: HMACSalt,
+        iv: IV,
+    },
+    /// The header of a message encrypted with the key-based API.
+    Key { iv: IV },
+}
+
+impl Header {
+    /// Parses the `Header` at the beginning of `message`, validating the version and options
+    /// bytes. Any bytes after the header are ignored.
+    pub fn parse(message: &[u8]) -> Result<Header> {
+        Header::parse_version(message, VERSION)
+    }
+
+    /// Like `parse`, but for older formats sharing the v3 header layout (i.e. v2).
+    pub(crate) fn parse_version(message: &[u8], expected_version: u8) -> Result<Header> {
+        if message.len() < 2 {
+            return Err(truncated(message.len()));
+        }
+
+        let version = message[0];
+        if version != expected_version {
+            return Err(Error::new(
+                ErrorKind::UnsupportedVersion(version),
+                format!("Unsupported version {}.", version),
+            ));
+        }
+
+        let options = message[1];
+        if options & !OPTION_USES_PASSWORD != 0 {
+            return Err(Error::new(
+                ErrorKind::UnknownOptions(options),
+                format!("Unknown options {:#04x}.", options),
+            ));
+        }
+
+        let header = if options & OPTION_USES_PASSWORD != 0 {
+            if message.len() < PASSWORD_HEADER_LEN {
+                return Err(truncated(message.len()));
+            }
+            Header::Password {
+                encryption_salt: Salt::try_from(&message[2..10])?,
+                hmac_salt: Salt::try_from(&message[10..18])?,
+                iv: IV::try_from(&message[18..PASSWORD_HEADER_LEN])?,
+            }
+        } else {
+            if message.len() < KEY_HEADER_LEN {
+                return Err(truncated(message.len()));
+            }
+            Header::Key {
+                iv: IV::try_from(&message[2..KEY_HEADER_LEN])?,
+            }
+        };
+
+        Ok(header)
+    }
+
+    /// The options byte matching this `Header`.
+    pub fn options(&self) -> u8 {
+        match *self {
+            Header::Password { .. } => OPTION_USES_PASSWORD,
+            Header::Key { .. } => 0,
+        }
+    }
+
+    /// The `IV` carried by this `Header`.
+    pub fn iv(&self) -> &IV {
+        match *self {
+            Header::Password { ref iv, .. } | Header::Key { ref iv } => iv,
+        }
+    }
+
+    /// The length of this `Header` once serialized.
+    pub fn encoded_len(&self) -> usize {
+        match *self {
+            Header::Password { .. } => PASSWORD_HEADER_LEN,
+            Header::Key { .. } => KEY_HEADER_LEN,
+        }
+    }
+
+    /// Serializes this `Header` into the bytes found on the wire.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_version(VERSION)
+    }
+
+    /// Like `to_bytes`, but for older formats sharing the v3 header layout (i.e. v2).
+    pub(crate) fn to_bytes_version(&self, version: u8) -> Vec<u8> {
+        let mut header = Vec::with_capacity(self.encoded_len());
+        header.push(version);
+        header.push(self.options());
+        if let Header::Password {
+            ref encryption_salt,
+            ref hmac_salt,
+            ..
+        } = *self
+        {
+            header.extend(encryption_salt.as_slice());
+            header.extend(hmac_salt.as_slice());
+        }
+        header.extend(self.iv().as_slice());
+        header
+    }
+}
+
+fn truncated(len: usize) -> Error {
+    Error::new(
+        ErrorKind::TruncatedHeader(len),
+        "The header is truncated.".to_owned(),
+    )
+}
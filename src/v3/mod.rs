@@ -1,22 +1,49 @@
-///! "Low-level" decryption abstractions.
+#[cfg(any(feature = "tokio", feature = "futures-io"))]
+mod adapter;
+/// Streaming encryption and decryption through the runtime-agnostic `futures-io` traits.
+#[cfg(feature = "futures-io")]
+pub mod async_futures;
+/// Streaming encryption and decryption through tokio's `AsyncWrite` and `AsyncRead`.
+#[cfg(feature = "tokio")]
+pub mod async_tokio;
+/// Length-prefixed framing of key-based messages as a `tokio_util` codec.
+#[cfg(feature = "codec")]
+pub mod codec;
+/// Push-style (`update`/`finalize`) encryption and sans-IO decryption.
+pub mod context;
+/// "Low-level" decryption abstractions.
 pub mod decryptor;
-///! "Low-level" encryption abstractions.
+/// "Low-level" encryption abstractions.
 pub mod encryptor;
-///! `Error` and `ErrorKind` types.
+/// `Error` and `ErrorKind` types.
 pub mod errors;
-///! The types.
+/// Parsing and serialization of the message `Header`.
+pub mod header;
+/// Parallel decryption of large messages.
+#[cfg(feature = "parallel")]
+mod parallel;
+/// Streaming decryption through `io::Read`.
+pub mod reader;
+/// Random-access decryption through `io::Read` and `io::Seek`.
+pub mod seekable;
+/// The types.
 pub mod types;
+/// Streaming encryption through `io::Write`.
+pub mod writer;
 
 use decryptor::Decryptor;
 use encryptor::Encryptor;
-use errors::Result;
-use types::{Message, PlainText, Salt, IV};
-
-///! Encrypts a `PlainText` with the given password, producing either an encrypted
-///! `Message` or an `Error` otherwise.
-///!
-///! **Note: This is NOT a streaming function.**
-pub fn encrypt(password: &str, plain_text: &PlainText) -> Result<Message> {
+use errors::{Error, ErrorKind, Result};
+use header::{Header, PASSWORD_HEADER_LEN};
+use rand::{CryptoRng, RngCore};
+use std::io::{Cursor, Read};
+use types::{EncryptionKey, HMACKey, KdfParams, Message, Password, PlainText, Salt, IV};
+
+/// Encrypts a `PlainText` with the given password, producing either an encrypted
+/// `Message` or an `Error` otherwise.
+///
+/// **Note: This is NOT a streaming function.**
+pub fn encrypt<P: Into<Password>>(password: P, plain_text: &PlainText) -> Result<Message> {
     let esalt = Salt::new()?;
     let hsalt = Salt::new()?;
     let iv = IV::new()?;
@@ -24,12 +51,84 @@ pub fn encrypt(password: &str, plain_text: &PlainText) -> Result<Message> {
     encryptor.encrypt(plain_text)
 }
 
+/// Like `encrypt`, but draws the salts and the `IV` out of the given cryptographically secure
+/// `rng` instead of the operating system's: e.g. a seeded one for reproducible tests, or the
+/// platform's on targets without an OS.
+///
+/// **Note: This is NOT a streaming function.**
+pub fn encrypt_with_rng<R: CryptoRng + RngCore, P: Into<Password>>(
+    rng: &mut R,
+    password: P,
+    plain_text: &PlainText,
+) -> Result<Message> {
+    let encryptor = Encryptor::from_password_with_rng(rng, password)?;
+    encryptor.encrypt(plain_text)
+}
+
 // TODO: Make API signature simmetric.
-///! Decrypts a `Message` with the given password, producing either a decrypted
-///! `Vec<u8>` or an `Error` otherwise.
-///!
-///! **Note: This is NOT a streaming function.**
-pub fn decrypt(password: &str, message: &Message) -> Result<Vec<u8>> {
+/// Decrypts a `Message` with the given password, producing either a decrypted
+/// `Vec<u8>` or an `Error` otherwise.
+///
+/// **Note: This is NOT a streaming function.**
+pub fn decrypt<P: Into<Password>>(password: P, message: &Message) -> Result<Vec<u8>> {
     let decryptor = Decryptor::from(password, message)?;
     decryptor.decrypt(message)
 }
+
+/// Checks that a password-based `Message` is authentic, without decrypting it.
+pub fn verify<P: Into<Password>>(password: P, message: &Message) -> Result<()> {
+    Decryptor::from(password, message)?.verify(message)
+}
+
+/// Checks that a password-based message streamed out of `reader` (positioned at its very
+/// beginning) is authentic, without decrypting it nor holding it in memory. The keys are derived
+/// as soon as the header has been read.
+pub fn verify_reader<P: Into<Password>, R: Read>(password: P, reader: R) -> Result<()> {
+    verify_reader_with_params(password, reader, &KdfParams::default())
+}
+
+/// Like `verify_reader`, but derives the keys with the given `KdfParams`.
+pub fn verify_reader_with_params<P: Into<Password>, R: Read>(
+    password: P,
+    mut reader: R,
+    params: &KdfParams,
+) -> Result<()> {
+    let mut header = [0; PASSWORD_HEADER_LEN];
+    let read = reader::read_up_to(&mut reader, &mut header).map_err(|e| {
+        Error::new(
+            ErrorKind::IOError(e),
+            "Reading the header failed.".to_owned(),
+        )
+    })?;
+    if read < PASSWORD_HEADER_LEN {
+        return Err(Error::new(
+            ErrorKind::NotEnoughInput(read),
+            "Decryption failed, not enough input.".to_owned(),
+        ));
+    }
+
+    let decryptor = Decryptor::from_header(&password.into(), Header::parse(&header)?, params)?;
+    decryptor.verify_reader(Cursor::new(header).chain(reader))
+}
+
+/// Checks that a `Message` produced by the key-based API is authentic, without decrypting it.
+pub fn verify_with_keys(
+    encryption_key: EncryptionKey,
+    hmac_key: HMACKey,
+    message: &Message,
+) -> Result<()> {
+    Decryptor::from_keys(encryption_key, hmac_key, message)?.verify(message)
+}
+
+/// Decrypts a `Message` produced by the key-based API with the given `EncryptionKey` and
+/// `HMACKey`, producing either a decrypted `Vec<u8>` or an `Error` otherwise.
+///
+/// **Note: This is NOT a streaming function.**
+pub fn decrypt_with_keys(
+    encryption_key: EncryptionKey,
+    hmac_key: HMACKey,
+    message: &Message,
+) -> Result<Vec<u8>> {
+    let decryptor = Decryptor::from_keys(encryption_key, hmac_key, message)?;
+    decryptor.decrypt(message)
+}
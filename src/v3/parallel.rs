@@ -0,0 +1,57 @@
+use super::{
+    errors::{Error, ErrorKind, Result},
+    types::*,
+};
+use aes::cipher::{
+    block_padding::{NoPadding, Padding, Pkcs7},
+    BlockDecryptMut, KeyIvInit,
+};
+use rayon::prelude::*;
+use zeroize::Zeroizing;
+
+type Aes256CbcDec = cbc::Decryptor<aes::Aes256>;
+
+const BLOCK_SIZE: usize = 16;
+/// How much cipher text each task decrypts. Must be a multiple of the block size.
+const CHUNK_SIZE: usize = 64 * 1024;
+/// Below this length, splitting the work costs more than it saves.
+pub(super) const MIN_LEN: usize = 4 * CHUNK_SIZE;
+
+/// Decrypts and unpads `cipher_text` on the rayon thread pool: every chunk is decrypted on its
+/// own, with the last cipher text block of the previous chunk as its `IV`.
+pub(super) fn plain_text(
+    encryption_key: &EncryptionKey,
+    iv: &IV,
+    cipher_text: &[u8],
+) -> Result<Zeroizing<Vec<u8>>> {
+    if cipher_text.is_empty() || cipher_text.len() % BLOCK_SIZE != 0 {
+        return Err(unpad_error());
+    }
+
+    // Decrypt in a buffer we own, so that it gets wiped should the unpadding fail.
+    let mut decrypted = Zeroizing::new(cipher_text.to_vec());
+    decrypted
+        .par_chunks_mut(CHUNK_SIZE)
+        .enumerate()
+        .for_each(|(index, chunk)| {
+            let iv = match index {
+                0 => iv.as_slice(),
+                _ => &cipher_text[index * CHUNK_SIZE - BLOCK_SIZE..index * CHUNK_SIZE],
+            };
+            Aes256CbcDec::new(encryption_key.as_slice().into(), iv.into())
+                .decrypt_padded_mut::<NoPadding>(chunk)
+                .expect("chunks are block-aligned");
+        });
+
+    let last = decrypted.len() - BLOCK_SIZE;
+    let len = last
+        + Pkcs7::unpad(aes::Block::from_slice(&decrypted[last..]))
+            .map_err(|_| unpad_error())?
+            .len();
+    decrypted.truncate(len);
+    Ok(decrypted)
+}
+
+fn unpad_error() -> Error {
+    Error::new(ErrorKind::UnpadError, "Unpad Error".to_owned())
+}
@@ -0,0 +1,146 @@
+use super::{
+    context::{DecryptionContext, Event},
+    errors::{Error, ErrorKind, Result},
+    header::PASSWORD_HEADER_LEN,
+    types::*,
+};
+use std::io::{self, Read};
+use zeroize::Zeroizing;
+
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// An `io::Read` adapter which decrypts a password-based RNCryptor message while reading it.
+///
+/// The trailing HMAC can only be checked once the whole message has been read, so plain text
+/// is handed out **before** it has been authenticated: a failed validation is reported as an
+/// `io::Error` (wrapping an `ErrorKind::AuthenticationFailed`) by the final `read`, and
+/// everything read until then must be discarded by the caller.
+pub struct DecryptingReader<R: Read> {
+    inner: R,
+    context: Option<DecryptionContext>,
+    plain_text: Zeroizing<Vec<u8>>,
+    position: usize,
+    failed: bool,
+}
+
+impl<R: Read> DecryptingReader<R> {
+    /// Reads the header out of `inner` and derives the keys from `password`.
+    pub fn from_password<P: Into<Password>>(password: P, inner: R) -> Result<DecryptingReader<R>> {
+        DecryptingReader::from_password_with_params(password, inner, &KdfParams::default())
+    }
+
+    /// Like `from_password`, but derives the keys with the given `KdfParams`.
+    pub fn from_password_with_params<P: Into<Password>>(
+        password: P,
+        mut inner: R,
+        params: &KdfParams,
+    ) -> Result<DecryptingReader<R>> {
+        let mut header = [0; PASSWORD_HEADER_LEN];
+        let read = read_up_to(&mut inner, &mut header).map_err(|e| {
+            Error::new(
+                ErrorKind::IOError(e),
+                "Reading the header failed.".to_owned(),
+            )
+        })?;
+        if read < PASSWORD_HEADER_LEN {
+            return Err(Error::new(
+                ErrorKind::NotEnoughInput(read),
+                "Decryption failed, not enough input.".to_owned(),
+            ));
+        }
+
+        let mut context = DecryptionContext::with_password_and_params(password, params);
+        match context.feed(&header) {
+            Event::Failed(error) => Err(error),
+            _ => Ok(DecryptingReader {
+                inner,
+                context: Some(context),
+                plain_text: Zeroizing::default(),
+                position: 0,
+                failed: false,
+            }),
+        }
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+}
+
+/// Hands the next `chunk` read out of a stream to the `context`, an empty one meaning the
+/// stream ended (and finishing the context). Shared by all the reading adapters.
+pub(super) fn advance(
+    context: &mut Option<DecryptionContext>,
+    chunk: &[u8],
+) -> Result<Zeroizing<Vec<u8>>> {
+    let event = match context.take() {
+        Some(finished) if chunk.is_empty() => finished.finish(),
+        Some(mut unfinished) => {
+            let event = unfinished.feed(chunk);
+            *context = Some(unfinished);
+            event
+        }
+        None => return Ok(Zeroizing::default()),
+    };
+    match event {
+        Event::PlainText(plain_text) | Event::Authenticated(plain_text) => Ok(plain_text),
+        Event::Failed(error) => Err(error),
+    }
+}
+
+impl<R: Read> Read for DecryptingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.plain_text.len() {
+            if self.failed {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Decryption previously failed.",
+                ));
+            }
+            if self.context.is_none() {
+                return Ok(0);
+            }
+
+            // Errors of the underlying reader (e.g. `WouldBlock`) consume no cipher text: they're
+            // passed through, and reading may be retried. Only a decryption failure is final.
+            let mut chunk = [0; CHUNK_SIZE];
+            let read = match self.inner.read(&mut chunk) {
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            match advance(&mut self.context, &chunk[..read]) {
+                Ok(plain_text) => {
+                    self.plain_text = plain_text;
+                    self.position = 0;
+                }
+                Err(e) => {
+                    self.failed = true;
+                    self.plain_text = Zeroizing::default();
+                    return Err(e.into());
+                }
+            }
+        }
+
+        let available = &self.plain_text[self.position..];
+        let read = available.len().min(buf.len());
+        buf[..read].copy_from_slice(&available[..read]);
+        self.position += read;
+        Ok(read)
+    }
+}
+
+/// Like `read_exact`, but returns how many bytes were read when hitting EOF early.
+pub(super) fn read_up_to<R: Read>(inner: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match inner.read(&mut buf[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(read)
+}
@@ -0,0 +1,227 @@
+use super::{
+    decryptor::{authentication_failed, Decryptor},
+    errors::{Error, ErrorKind, Result},
+    types::*,
+};
+use aes::{
+    cipher::{generic_array::GenericArray, BlockDecrypt, KeyInit},
+    Aes256,
+};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::io::{self, Read, Seek, SeekFrom};
+use zeroize::Zeroizing;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const BLOCK_SIZE: usize = 16;
+/// The largest amount of cipher text decrypted by a single `read`.
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// A random-access decryptor for messages stored in a `Read + Seek` input (typically a file),
+/// which decrypts only the blocks covering the requested plain text: as CBC decryption of a
+/// block only needs the previous cipher text block, reading at any offset is cheap.
+///
+/// The message may start anywhere in the input: its position when the `SeekableDecryptor` is
+/// built is taken as the beginning of the message. Offsets seen through `Seek` are plain text
+/// offsets.
+pub struct SeekableDecryptor<R: Read + Seek> {
+    inner: R,
+    cipher: Aes256,
+    iv: IV,
+    /// Where the cipher text starts in `inner`.
+    cipher_text_start: u64,
+    plain_text_len: u64,
+    position: u64,
+}
+
+impl<R: Read + Seek> SeekableDecryptor<R> {
+    /// Wraps the message in `inner`, after validating its HMAC: this reads the whole message
+    /// once, up front. The blocks read afterwards are read again from `inner` and aren't checked
+    /// any further, so they're only authentic as long as `inner` can't change after this check
+    /// (a file being rewritten, or an HTTP range source serving a new object, would go
+    /// unnoticed and yield garbage rather than an error).
+    pub fn new(decryptor: &Decryptor, inner: R) -> Result<SeekableDecryptor<R>> {
+        SeekableDecryptor::build(decryptor, inner, true)
+    }
+
+    /// Wraps the message in `inner` **without** validating its HMAC, for messages which have
+    /// already been verified earlier (e.g. when they were stored) and can't have changed since.
+    /// Should the message have been tampered with anyway, garbage is read rather than an error
+    /// reported.
+    pub fn verified_earlier(decryptor: &Decryptor, inner: R) -> Result<SeekableDecryptor<R>> {
+        SeekableDecryptor::build(decryptor, inner, false)
+    }
+
+    fn build(decryptor: &Decryptor, mut inner: R, verify: bool) -> Result<SeekableDecryptor<R>> {
+        let start = inner.stream_position().map_err(io_error)?;
+        let end = inner.seek(SeekFrom::End(0)).map_err(io_error)?;
+        let header = decryptor.header().to_bytes_version(decryptor.version);
+        let (header_len, message_len) = (header.len() as u64, end.saturating_sub(start));
+        if message_len < header_len + (BLOCK_SIZE + HMAC_LEN) as u64 {
+            return Err(Error::new(
+                ErrorKind::NotEnoughInput(message_len as usize),
+                "Decryption failed, not enough input.".to_owned(),
+            ));
+        }
+        let cipher_text_len = message_len - header_len - HMAC_LEN as u64;
+        if cipher_text_len % BLOCK_SIZE as u64 != 0 {
+            return Err(Error::new(
+                ErrorKind::WrongInputSize(cipher_text_len as usize),
+                "The cipher text is not block-aligned.".to_owned(),
+            ));
+        }
+
+        // The `Decryptor` must have been built for this very message.
+        let mut actual_header = vec![0; header.len()];
+        inner.seek(SeekFrom::Start(start)).map_err(io_error)?;
+        inner.read_exact(&mut actual_header).map_err(io_error)?;
+        if actual_header != header {
+            return Err(authentication_failed());
+        }
+        if verify {
+            verify_hmac(&mut inner, &decryptor.hmac_key, &header, cipher_text_len)?;
+        }
+
+        let mut seekable = SeekableDecryptor {
+            inner,
+            cipher: Aes256::new(decryptor.encryption_key.as_slice().into()),
+            iv: decryptor.iv.clone(),
+            cipher_text_start: start + header_len,
+            plain_text_len: cipher_text_len,
+            position: 0,
+        };
+        let last_block = cipher_text_len / BLOCK_SIZE as u64 - 1;
+        let last = seekable.decrypt_blocks(last_block, 1).map_err(io_error)?;
+        let padding = last[BLOCK_SIZE - 1];
+        if padding == 0
+            || padding as usize > BLOCK_SIZE
+            || last[BLOCK_SIZE - padding as usize..]
+                .iter()
+                .any(|&byte| byte != padding)
+        {
+            return Err(authentication_failed());
+        }
+        seekable.plain_text_len -= padding as u64;
+        Ok(seekable)
+    }
+
+    /// The length of the (unpadded) plain text.
+    pub fn plain_text_len(&self) -> u64 {
+        self.plain_text_len
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Decrypts `count` blocks starting at the `first` one, reading the previous cipher text
+    /// block (or using the `IV`) to undo the chaining.
+    fn decrypt_blocks(&mut self, first: u64, count: usize) -> io::Result<Zeroizing<Vec<u8>>> {
+        let mut blocks = Zeroizing::new(vec![0; (count + 1) * BLOCK_SIZE]);
+        if first == 0 {
+            blocks[..BLOCK_SIZE].copy_from_slice(self.iv.as_slice());
+            self.inner.seek(SeekFrom::Start(self.cipher_text_start))?;
+            self.inner.read_exact(&mut blocks[BLOCK_SIZE..])?;
+        } else {
+            let offset = (first - 1) * BLOCK_SIZE as u64;
+            self.inner
+                .seek(SeekFrom::Start(self.cipher_text_start + offset))?;
+            self.inner.read_exact(&mut blocks)?;
+        }
+
+        let mut plain_text = Zeroizing::new(blocks[BLOCK_SIZE..].to_vec());
+        for (block, previous) in plain_text
+            .chunks_exact_mut(BLOCK_SIZE)
+            .zip(blocks.chunks_exact(BLOCK_SIZE))
+        {
+            self.cipher
+                .decrypt_block(GenericArray::from_mut_slice(block));
+            block
+                .iter_mut()
+                .zip(previous)
+                .for_each(|(byte, chained)| *byte ^= chained);
+        }
+        Ok(plain_text)
+    }
+}
+
+impl<R: Read + Seek> Read for SeekableDecryptor<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || self.position >= self.plain_text_len {
+            return Ok(0);
+        }
+
+        let wanted = (buf.len() as u64)
+            .min(self.plain_text_len - self.position)
+            .min(CHUNK_SIZE as u64) as usize;
+        let first = self.position / BLOCK_SIZE as u64;
+        let skip = (self.position % BLOCK_SIZE as u64) as usize;
+        let count = (skip + wanted).div_ceil(BLOCK_SIZE);
+        let plain_text = self.decrypt_blocks(first, count)?;
+
+        buf[..wanted].copy_from_slice(&plain_text[skip..skip + wanted]);
+        self.position += wanted as u64;
+        Ok(wanted)
+    }
+}
+
+impl<R: Read + Seek> Seek for SeekableDecryptor<R> {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match position {
+            SeekFrom::Start(offset) => {
+                self.position = offset;
+                return Ok(offset);
+            }
+            SeekFrom::End(offset) => (self.plain_text_len, offset),
+            SeekFrom::Current(offset) => (self.position, offset),
+        };
+        match base.checked_add_signed(offset) {
+            Some(position) => {
+                self.position = position;
+                Ok(position)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Invalid seek to a negative or overflowing position.",
+            )),
+        }
+    }
+}
+
+/// Streams the whole message through the HMAC, comparing the result with the trailing one.
+fn verify_hmac<R: Read>(
+    inner: &mut R,
+    hmac_key: &HMACKey,
+    header: &[u8],
+    cipher_text_len: u64,
+) -> Result<()> {
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(hmac_key.as_slice())
+        .map_err(|error| Error::new(ErrorKind::HMACGenerationFailed, error.to_string()))?;
+    mac.update(header);
+
+    let mut chunk = vec![0; CHUNK_SIZE];
+    let mut remaining = cipher_text_len;
+    while remaining > 0 {
+        let len = remaining.min(CHUNK_SIZE as u64) as usize;
+        inner.read_exact(&mut chunk[..len]).map_err(io_error)?;
+        mac.update(&chunk[..len]);
+        remaining -= len as u64;
+    }
+
+    let mut hmac = [0; HMAC_LEN];
+    inner.read_exact(&mut hmac).map_err(io_error)?;
+    let computed_hmac = HMAC(mac.finalize().into_bytes().into());
+    if !HMAC(hmac).is_equal_in_consistent_time_to(&computed_hmac) {
+        return Err(authentication_failed());
+    }
+    Ok(())
+}
+
+fn io_error(error: io::Error) -> Error {
+    Error::new(
+        ErrorKind::IOError(error),
+        "Reading the message failed.".to_owned(),
+    )
+}
@@ -1,91 +1,264 @@
-use super::errors::{Error, ErrorKind, Result};
+use super::{
+    errors::{Error, ErrorKind, Result},
+    header::Header,
+};
 use hmac::{Hmac, Mac};
-use rand::{rngs::OsRng, RngCore};
+use rand::{rngs::OsRng, CryptoRng, RngCore};
 use sha2::Sha256;
 use std::{
-    fmt::{Display, Formatter, Result as FmtResult},
+    fmt::{Debug, Display, Formatter, Result as FmtResult},
     num::NonZeroU32,
     result::Result as StdResult,
 };
+use subtle::ConstantTimeEq;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
 type HmacSha256 = Hmac<Sha256>;
 
+/// The length of an `EncryptionKey` or of an `HMACKey` (AES-256 and HMAC-SHA256) in bytes.
+pub const KEY_LEN: usize = 32;
+/// The length of an `IV` (the AES block size) in bytes.
+pub const IV_LEN: usize = 16;
+/// The length of a `Salt` in bytes.
+pub const SALT_LEN: usize = 8;
+
+/// Implements the conversions shared by all the fixed-size byte wrappers: infallible from an
+/// array of the right size, fallible (`ErrorKind::WrongInputSize`) from slices, vectors and
+/// hex strings.
+macro_rules! fixed_size_bytes {
+    ($name:ident, $len:expr) => {
+        impl $name {
+            /// Parses a hex string (whitespace is ignored) of exactly the expected length. The
+            /// intermediate buffers are wiped, as they may hold key material.
+            pub fn from_hex(hex: &str) -> Result<$name> {
+                let hex: Zeroizing<String> =
+                    Zeroizing::new(hex.chars().filter(|c| !c.is_whitespace()).collect());
+                let bytes = Zeroizing::new(
+                    hex::decode(hex.as_str())
+                        .map_err(|error| Error::new(ErrorKind::InvalidHex, error.to_string()))?,
+                );
+                $name::try_from(bytes.as_slice())
+            }
+
+            /// Turns the value into a `[u8]` slice.
+            pub fn as_slice(&self) -> &[u8] {
+                &self.0
+            }
+        }
+
+        impl From<[u8; $len]> for $name {
+            fn from(bytes: [u8; $len]) -> $name {
+                $name(bytes)
+            }
+        }
+
+        impl TryFrom<&[u8]> for $name {
+            type Error = Error;
+
+            fn try_from(bytes: &[u8]) -> Result<$name> {
+                let bytes = bytes.try_into().map_err(|_| {
+                    Error::new(
+                        ErrorKind::WrongInputSize(bytes.len()),
+                        format!(
+                            "{} must be {} bytes long, got {}.",
+                            stringify!($name),
+                            $len,
+                            bytes.len()
+                        ),
+                    )
+                })?;
+                Ok($name(bytes))
+            }
+        }
+
+        impl TryFrom<Vec<u8>> for $name {
+            type Error = Error;
+
+            /// Consumes `bytes`, wiping them once copied out (or rejected).
+            fn try_from(bytes: Vec<u8>) -> Result<$name> {
+                let bytes = Zeroizing::new(bytes);
+                $name::try_from(bytes.as_slice())
+            }
+        }
+
+        impl AsRef<[u8]> for $name {
+            fn as_ref(&self) -> &[u8] {
+                &self.0
+            }
+        }
+    };
+}
+
+/// Implements a `Debug` which never prints the (secret) content.
+macro_rules! redacted_debug {
+    ($name:ident) => {
+        impl Debug for $name {
+            fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+                write!(f, "{}(<redacted>)", stringify!($name))
+            }
+        }
+    };
+}
+
 /// An `EncryptionKey`, which can be constructed from a `EncryptionSalt` and a password.
-#[derive(Clone, Debug)]
-pub struct EncryptionKey(Vec<u8>);
+/// It's wiped from memory when dropped.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct EncryptionKey([u8; KEY_LEN]);
+
+fixed_size_bytes!(EncryptionKey, KEY_LEN);
+redacted_debug!(EncryptionKey);
 
 impl<'a> EncryptionKey {
     /// Creates a new `EncryptionKey` out of an `EncryptionSalt` and a password.
     pub fn new(encryption_salt: &EncryptionSalt, password: &'a [u8]) -> EncryptionKey {
-        EncryptionKey(new_key_with_salt(encryption_salt, password))
+        EncryptionKey::new_with_params(encryption_salt, password, &KdfParams::default())
     }
 
-    pub fn from(raw_key: Vec<u8>) -> EncryptionKey {
-        EncryptionKey(raw_key)
-    }
-
-    pub fn to_vec(&self) -> &Vec<u8> {
-        let EncryptionKey(ref v) = *self;
-        v
+    /// Like `new`, but derives the key with the given `KdfParams`.
+    pub fn new_with_params(
+        encryption_salt: &EncryptionSalt,
+        password: &'a [u8],
+        params: &KdfParams,
+    ) -> EncryptionKey {
+        let mut key = EncryptionKey([0; KEY_LEN]);
+        new_key_with_salt(encryption_salt, password, params, &mut key.0);
+        key
     }
 }
 
 /// A `Salt`, which can be completely random or user-constructed.
-#[derive(Clone, Debug)]
-pub struct Salt(pub Vec<u8>);
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Salt(pub [u8; SALT_LEN]);
+
+fixed_size_bytes!(Salt, SALT_LEN);
 
 impl Salt {
     /// Creates a new, completely random `Salt` of 8 bytes.
     pub fn new() -> Result<Salt> {
-        match random_data_of_len(8) {
+        Salt::new_with_rng(&mut OsRng)
+    }
+
+    /// Like `new`, but draws the bytes out of the given cryptographically secure `rng`.
+    pub fn new_with_rng<R: CryptoRng + RngCore>(rng: &mut R) -> Result<Salt> {
+        let mut salt = [0; SALT_LEN];
+        match random_data(rng, &mut salt) {
             Err(e) => Err(Error::new(
                 ErrorKind::SaltGenerationFailed(e),
                 "Salt Generation failed.".to_owned(),
             )),
-            Ok(v) => Ok(Salt(v)),
+            Ok(()) => Ok(Salt(salt)),
         }
     }
+}
+
+/// A `HMACKey`, which can be constructed from an `HMACSalt` and a password.
+/// It's wiped from memory when dropped, and compared in constant time.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct HMACKey([u8; KEY_LEN]);
 
-    /// Turns a `Salt` into a `[u8]` slice.
-    pub fn as_slice(&self) -> &[u8] {
-        let Salt(ref s) = *self;
-        s
+fixed_size_bytes!(HMACKey, KEY_LEN);
+redacted_debug!(HMACKey);
+
+impl PartialEq for HMACKey {
+    fn eq(&self, HMACKey(other): &HMACKey) -> bool {
+        self.0.ct_eq(other).into()
     }
 }
 
-/// A `HMACKey`, which can be constructed from an `HMACSalt` and a password.
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub struct HMACKey(Vec<u8>);
+impl Eq for HMACKey {}
 
-fn new_key_with_salt(salt: &Salt, password: &[u8]) -> Vec<u8> {
-    use ring::pbkdf2::{derive, PBKDF2_HMAC_SHA1};
+/// The pseudo-random function PBKDF2 is instantiated with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Prf {
+    /// HMAC-SHA1, the one mandated by the spec.
+    HmacSha1,
+    /// HMAC-SHA256.
+    HmacSha256,
+    /// HMAC-SHA512.
+    HmacSha512,
+}
 
-    let Salt(ref salt) = *salt;
-    let mut result = vec![0;32];
+/// The PBKDF2 parameters used to derive keys out of a password.
+///
+/// The spec mandates 10,000 iterations of PBKDF2-HMAC-SHA1, which is what `default` returns.
+/// Anything else has to be built explicitly through `non_standard`, and won't interoperate with
+/// other RNCryptor implementations unless they've been changed in the same way.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KdfParams {
+    iterations: NonZeroU32,
+    prf: Prf,
+}
+
+impl KdfParams {
+    /// The parameters mandated by the v3 spec.
+    pub const V3: KdfParams = KdfParams {
+        iterations: match NonZeroU32::new(10_000) {
+            Some(iterations) => iterations,
+            None => unreachable!(),
+        },
+        prf: Prf::HmacSha1,
+    };
+
+    /// Builds **non-standard** parameters, for interoperating with forks of the format or for
+    /// speeding up tests.
+    pub fn non_standard(iterations: NonZeroU32, prf: Prf) -> KdfParams {
+        KdfParams { iterations, prf }
+    }
+
+    /// The number of PBKDF2 iterations.
+    pub fn iterations(&self) -> NonZeroU32 {
+        self.iterations
+    }
+
+    /// The pseudo-random function PBKDF2 is instantiated with.
+    pub fn prf(&self) -> Prf {
+        self.prf
+    }
 
-    let iterations = NonZeroU32::new(10_000).expect("zero iterations when non-zero OwO");
-    derive(PBKDF2_HMAC_SHA1, iterations, &salt[..], password, &mut result);
+    /// Whether these are the parameters mandated by the spec.
+    pub fn is_standard(&self) -> bool {
+        *self == KdfParams::V3
+    }
+}
 
-    result
+impl Default for KdfParams {
+    fn default() -> KdfParams {
+        KdfParams::V3
+    }
+}
+
+/// Derives a key straight into `result`, so that no copy of it is left around.
+fn new_key_with_salt(salt: &Salt, password: &[u8], params: &KdfParams, result: &mut [u8; KEY_LEN]) {
+    use ring::pbkdf2::{derive, PBKDF2_HMAC_SHA1, PBKDF2_HMAC_SHA256, PBKDF2_HMAC_SHA512};
+
+    let Salt(ref salt) = *salt;
+
+    let algorithm = match params.prf {
+        Prf::HmacSha1 => PBKDF2_HMAC_SHA1,
+        Prf::HmacSha256 => PBKDF2_HMAC_SHA256,
+        Prf::HmacSha512 => PBKDF2_HMAC_SHA512,
+    };
+    derive(algorithm, params.iterations, &salt[..], password, result);
 }
 
 impl<'a> HMACKey {
     pub fn new(hmac_salt: &Salt, password: &'a [u8]) -> HMACKey {
-        HMACKey(new_key_with_salt(hmac_salt, password))
+        HMACKey::new_with_params(hmac_salt, password, &KdfParams::default())
     }
 
-    pub fn from(raw_key: Vec<u8>) -> HMACKey {
-        HMACKey(raw_key)
+    /// Like `new`, but derives the key with the given `KdfParams`.
+    pub fn new_with_params(hmac_salt: &Salt, password: &'a [u8], params: &KdfParams) -> HMACKey {
+        let mut key = HMACKey([0; KEY_LEN]);
+        new_key_with_salt(hmac_salt, password, params, &mut key.0);
+        key
     }
 }
 
-/// A RNCryptor `Header` built during the encryption/decryption process.
-#[derive(Clone, Debug)]
-pub struct Header(pub Vec<u8>);
-
 /// An `IV` (Initialization Vector) which can be completely random or user constructed.
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct IV(Vec<u8>);
+pub struct IV([u8; IV_LEN]);
+
+fixed_size_bytes!(IV, IV_LEN);
 
 impl Display for IV {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
@@ -95,78 +268,126 @@ impl Display for IV {
     }
 }
 
-/// A password.
-pub type Password = [u8];
+/// A password, which is wiped from memory when dropped. Hand over an owned `String` (or
+/// `Vec<u8>`) to make sure no other copy of it is left behind.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct Password(Vec<u8>);
+
+redacted_debug!(Password);
+
+impl Password {
+    /// The password as bytes, as fed to the key derivation.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Whether the password is empty.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<&str> for Password {
+    fn from(password: &str) -> Password {
+        Password(password.as_bytes().to_vec())
+    }
+}
+
+impl From<String> for Password {
+    fn from(password: String) -> Password {
+        Password(password.into_bytes())
+    }
+}
+
+impl From<Vec<u8>> for Password {
+    fn from(password: Vec<u8>) -> Password {
+        Password(password)
+    }
+}
+
+impl From<Zeroizing<String>> for Password {
+    fn from(password: Zeroizing<String>) -> Password {
+        Password(password.as_bytes().to_vec())
+    }
+}
 /// A plain text, which is something not encrypted.
 pub type PlainText = [u8];
 // TODO: Can we make CipherText & Message to be isomorphic?
 /// An encrypted message, the result of the encryption process.
 pub type Message = Vec<u8>;
 
-fn random_data_of_len(size: usize) -> StdResult<Vec<u8>, std::io::Error> {
-    let mut data = vec![0; size];
-    OsRng.try_fill_bytes(&mut data)?;
-    Ok(data)
+fn random_data<R: CryptoRng + RngCore>(
+    rng: &mut R,
+    data: &mut [u8],
+) -> StdResult<(), std::io::Error> {
+    rng.try_fill_bytes(data)?;
+    Ok(())
 }
 
 impl IV {
     /// Creates a new, completely random `IV` (Initialization Vector) of 16 bytes.
     pub fn new() -> Result<IV> {
-        match random_data_of_len(16) {
+        IV::new_with_rng(&mut OsRng)
+    }
+
+    /// Like `new`, but draws the bytes out of the given cryptographically secure `rng`.
+    pub fn new_with_rng<R: CryptoRng + RngCore>(rng: &mut R) -> Result<IV> {
+        let mut iv = [0; IV_LEN];
+        match random_data(rng, &mut iv) {
             Err(e) => Err(Error::new(
                 ErrorKind::IVGenerationFailed(e),
                 "IV Generation failed.".to_owned(),
             )),
-            Ok(v) => Ok(IV(v)),
+            Ok(()) => Ok(IV(iv)),
         }
     }
-
-    /// Creates a new `IV` (Initialization Vector) from a `Vec<u8>`. It's your responsibility
-    /// to ensure the `IV` is random and of 16 bytes.
-    pub fn from(raw_key: Vec<u8>) -> IV {
-        IV(raw_key)
-    }
-
-    /// Turns the `IV` into a `[u8]` slice.
-    pub fn as_slice(&self) -> &[u8] {
-        let IV(ref s) = *self;
-        s
-    }
-
-    /// Turns the `IV` into a `Vec<u8>` vector.
-    pub fn to_vec(&self) -> &Vec<u8> {
-        let IV(ref v) = *self;
-        v
-    }
 }
 
 /// An `CipherText`, essentially a wrapper around a `Vec<u8>`.
 #[derive(Debug)]
 pub struct CipherText(pub Vec<u8>);
 
+/// The length of an `HMAC` (HMAC-SHA256) in bytes.
+pub const HMAC_LEN: usize = 32;
+
 /// An `HMAC`, which can be constructed out of an `Header`, some bytes and an `HMACKey`.
 #[derive(Debug)]
-pub struct HMAC(pub Vec<u8>);
+pub struct HMAC(pub [u8; HMAC_LEN]);
 
 impl HMAC {
-    pub fn new(&Header(ref h): &Header, txt: &[u8], &HMACKey(ref key): &HMACKey) -> Result<HMAC> {
-        let mut input = Vec::new();
-        input.extend(h);
-        input.extend(txt);
+    pub fn new(header: &Header, txt: &[u8], hmac_key: &HMACKey) -> Result<HMAC> {
+        HMAC::over(&header.to_bytes(), txt, hmac_key)
+    }
 
+    /// Computes the `HMAC` of already serialized header bytes followed by `txt`.
+    pub(crate) fn over(header: &[u8], txt: &[u8], HMACKey(key): &HMACKey) -> Result<HMAC> {
         let mut mac = HmacSha256::new_from_slice(key)
             .map_err(|error| Error::new(ErrorKind::HMACGenerationFailed, error.to_string()))?;
-        mac.update(&input);
+        mac.update(header);
+        mac.update(txt);
+
+        Ok(HMAC(mac.finalize().into_bytes().into()))
+    }
 
-        let result = mac.finalize().into_bytes().to_vec();
-        Ok(HMAC(result))
+    /// Reads an `HMAC` out of a slice, failing unless it's exactly `HMAC_LEN` bytes long.
+    pub fn from_slice(raw_hmac: &[u8]) -> Result<HMAC> {
+        let hmac = raw_hmac.try_into().map_err(|_| {
+            Error::new(
+                ErrorKind::HMACNotFound,
+                format!(
+                    "Expected an HMAC of {} bytes, got {}.",
+                    HMAC_LEN,
+                    raw_hmac.len()
+                ),
+            )
+        })?;
+        Ok(HMAC(hmac))
     }
 
-    pub fn is_equal_in_consistent_time_to(&self, &HMAC(ref other): &HMAC) -> bool {
+    /// Compares two `HMAC`s in constant time.
+    pub fn is_equal_in_consistent_time_to(&self, HMAC(other): &HMAC) -> bool {
         let HMAC(ref this) = *self;
-        this.iter()
-            .zip(other.iter())
-            .fold(true, |acc, (x, y)| acc && (x == y))
+        this.ct_eq(other).into()
     }
 }
 
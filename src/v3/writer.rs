@@ -0,0 +1,87 @@
+use super::{context::EncryptionContext, errors::Result, types::*};
+use std::io::{self, Write};
+
+/// An `io::Write` adapter which encrypts everything written into it, streaming the resulting
+/// RNCryptor message into the wrapped writer.
+///
+/// The header is written as soon as the first bytes come in, full AES blocks are encrypted and
+/// forwarded eagerly, and the HMAC is computed incrementally. Call `finish` once done, which
+/// emits the padded last block and the HMAC: dropping the writer without calling it leaves a
+/// truncated (and therefore invalid) message behind.
+///
+/// Should the wrapped writer fail (e.g. with `WouldBlock`) once some input has been taken in,
+/// the encrypted bytes it didn't accept are kept and written out first by the next call to
+/// `write`, `flush` or `finish`, so that retrying never encrypts anything twice.
+pub struct EncryptingWriter<W: Write> {
+    inner: W,
+    context: EncryptionContext,
+    output: Vec<u8>,
+    /// How much of `output` has been written out already.
+    written: usize,
+}
+
+impl<W: Write> EncryptingWriter<W> {
+    /// Takes the already serialized `header`, so that the writer is oblivious to the version
+    /// of the format it writes.
+    pub(super) fn new(
+        encryption_key: &EncryptionKey,
+        hmac_key: &HMACKey,
+        header: Vec<u8>,
+        iv: &IV,
+        inner: W,
+    ) -> Result<EncryptingWriter<W>> {
+        Ok(EncryptingWriter {
+            inner,
+            context: EncryptionContext::new(encryption_key, hmac_key, header, iv)?,
+            output: Vec::new(),
+            written: 0,
+        })
+    }
+
+    /// Gets a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Completes the message by writing the PKCS7-padded last block and the HMAC, flushes
+    /// and returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.drain()?;
+        self.context.finalize_into(&mut self.output);
+        self.inner.write_all(&self.output)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    /// Writes out whatever was output so far.
+    fn drain(&mut self) -> io::Result<()> {
+        while self.written < self.output.len() {
+            match self.inner.write(&self.output[self.written..]) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(written) => self.written += written,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        self.output.clear();
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for EncryptingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.drain()?;
+        self.context.update_into(buf, &mut self.output);
+        // `buf` has been taken in for good: should the wrapped writer fail now, the error comes
+        // out of the next call instead, which starts by writing out what's left.
+        let _ = self.drain();
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.drain()?;
+        self.inner.flush()
+    }
+}
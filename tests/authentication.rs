@@ -0,0 +1,43 @@
+mod common;
+
+use common::{authenticated_message_with_bad_padding, decryptor, message};
+use rncryptor::v3::errors::ErrorKind;
+
+#[test]
+fn tampered_cipher_text_is_opaque() {
+    let mut message = message(b"some secret data, longer than a block");
+    message[40] ^= 1;
+
+    let error = decryptor(&message).decrypt(&message).unwrap_err();
+    assert!(matches!(error.kind, ErrorKind::AuthenticationFailed))
+}
+
+#[test]
+fn bad_padding_is_opaque() {
+    let message = authenticated_message_with_bad_padding(16);
+
+    let error = decryptor(&message).decrypt(&message).unwrap_err();
+    assert!(matches!(error.kind, ErrorKind::AuthenticationFailed))
+}
+
+#[test]
+fn diagnostics_report_the_hmac_mismatch() {
+    let mut message = message(b"some secret data");
+    let last = message.len() - 1;
+    message[last] ^= 1;
+
+    let error = decryptor(&message)
+        .decrypt_with_diagnostics(&message)
+        .unwrap_err();
+    assert!(matches!(error.kind, ErrorKind::HMACValidationFailed))
+}
+
+#[test]
+fn diagnostics_report_the_bad_padding() {
+    let message = authenticated_message_with_bad_padding(16);
+
+    let error = decryptor(&message)
+        .decrypt_with_diagnostics(&message)
+        .unwrap_err();
+    assert!(matches!(error.kind, ErrorKind::UnpadError))
+}
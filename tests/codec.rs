@@ -0,0 +1,138 @@
+#![cfg(feature = "codec")]
+
+mod common;
+
+use bytes::{Bytes, BytesMut};
+use common::error_kind;
+use futures::{SinkExt, StreamExt};
+use rncryptor::v3::{self, codec::MessageCodec, errors::ErrorKind, types::*};
+use tokio_util::codec::{Decoder, Encoder, Framed};
+
+fn codec() -> MessageCodec {
+    MessageCodec::new(EncryptionKey::from([1; 32]), HMACKey::from([2; 32]))
+}
+
+#[test]
+fn frames_are_length_prefixed_messages() {
+    let mut frame = BytesMut::new();
+    codec()
+        .encode(Bytes::from_static(b"hello"), &mut frame)
+        .unwrap();
+    let message = &frame[4..];
+
+    assert_eq!(frame[..4], (message.len() as u32).to_be_bytes());
+    assert_eq!(
+        v3::decrypt_with_keys(
+            EncryptionKey::from([1; 32]),
+            HMACKey::from([2; 32]),
+            &message.to_vec()
+        )
+        .unwrap(),
+        b"hello".to_vec()
+    );
+}
+
+#[test]
+fn waits_for_complete_frames() {
+    let mut frame = BytesMut::new();
+    let mut codec = codec();
+    codec
+        .encode(Bytes::from_static(b"hello"), &mut frame)
+        .unwrap();
+    let mut partial = frame.split_to(frame.len() - 1);
+
+    assert!(codec.decode(&mut partial).unwrap().is_none());
+    partial.extend_from_slice(&frame);
+    assert_eq!(
+        codec.decode(&mut partial).unwrap().unwrap(),
+        Bytes::from_static(b"hello")
+    );
+}
+
+#[test]
+fn uses_a_fresh_iv_per_message() {
+    let (mut first, mut second) = (BytesMut::new(), BytesMut::new());
+    let mut codec = codec();
+    codec
+        .encode(Bytes::from_static(b"hello"), &mut first)
+        .unwrap();
+    codec
+        .encode(Bytes::from_static(b"hello"), &mut second)
+        .unwrap();
+
+    assert_ne!(first, second);
+}
+
+#[test]
+fn rejects_tampered_frames() {
+    let mut frame = BytesMut::new();
+    let mut codec = codec();
+    codec
+        .encode(Bytes::from_static(b"hello"), &mut frame)
+        .unwrap();
+    let last = frame.len() - 1;
+    frame[last] ^= 1;
+    let error = codec.decode(&mut frame).unwrap_err();
+
+    assert!(matches!(
+        error_kind(&error),
+        ErrorKind::AuthenticationFailed
+    ));
+}
+
+#[test]
+fn honours_the_framing_configuration() {
+    let mut codec =
+        MessageCodec::with_framing(EncryptionKey::from([1; 32]), HMACKey::from([2; 32]), 2, 100)
+            .unwrap();
+    let mut frame = BytesMut::new();
+    codec
+        .encode(Bytes::from_static(b"hello"), &mut frame)
+        .unwrap();
+
+    assert_eq!(frame.len(), 2 + 18 + 16 + 32);
+    assert!(codec.encode(Bytes::from(vec![0; 100]), &mut frame).is_err());
+
+    let mut oversized = BytesMut::from(&[0x10, 0x00][..]);
+    assert!(codec.decode(&mut oversized).is_err());
+}
+
+#[test]
+fn rejects_unsupported_length_prefixes() {
+    for length_field_len in [0, 9] {
+        let result = MessageCodec::with_framing(
+            EncryptionKey::from([1; 32]),
+            HMACKey::from([2; 32]),
+            length_field_len,
+            100,
+        );
+        assert!(matches!(
+            result.err().unwrap().kind,
+            ErrorKind::WrongInputSize(len) if len == length_field_len
+        ));
+    }
+}
+
+#[tokio::test]
+async fn round_trips_over_a_duplex_stream() {
+    let (client, server) = tokio::io::duplex(64);
+    let mut client = Framed::new(client, codec());
+    let mut server = Framed::new(server, codec());
+    let items: Vec<Bytes> = (0..20u8)
+        .map(|i| Bytes::from(vec![i; i as usize * 10]))
+        .collect();
+
+    let to_send = items.clone();
+    let sender = tokio::spawn(async move {
+        for item in to_send {
+            client.send(item).await.unwrap();
+        }
+    });
+    let mut received = Vec::new();
+    while let Some(item) = server.next().await {
+        received.push(item.unwrap());
+    }
+    sender.await.unwrap();
+
+    assert_eq!(received, items);
+}
@@ -0,0 +1,64 @@
+//! Fixtures shared by the integration tests: fixed keys, and key-based messages built with them.
+// Every test crate only uses some of these.
+#![allow(dead_code)]
+
+use rncryptor::v3::{
+    decryptor::Decryptor,
+    encryptor::Encryptor,
+    errors::{Error, ErrorKind},
+    header::Header,
+    types::*,
+};
+use std::io;
+
+pub fn keys() -> (EncryptionKey, HMACKey) {
+    (EncryptionKey::from([1; 32]), HMACKey::from([2; 32]))
+}
+
+/// An `Encryptor` using `keys` and a fixed `IV`.
+pub fn encryptor() -> Encryptor {
+    let (encryption_key, hmac_key) = keys();
+    Encryptor::from_keys_with_fixed_iv(encryption_key, hmac_key, IV::from([3; 16])).unwrap()
+}
+
+/// The key-based message `encryptor` makes out of `plain_text`.
+pub fn message(plain_text: &[u8]) -> Vec<u8> {
+    encryptor().encrypt(plain_text).unwrap()
+}
+
+pub fn decryptor(message: &[u8]) -> Decryptor {
+    let (encryption_key, hmac_key) = keys();
+    Decryptor::from_keys(encryption_key, hmac_key, message).unwrap()
+}
+
+/// A plain text of `len` bytes which doesn't repeat every block.
+pub fn plain_text(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+/// A message carrying a valid HMAC over a cipher text of `len` bytes (a multiple of 16) whose
+/// padding is invalid.
+pub fn authenticated_message_with_bad_padding(len: usize) -> Vec<u8> {
+    let (encryption_key, hmac_key) = keys();
+    let iv = IV::from([3; 16]);
+    let header = Header::Key { iv: iv.clone() };
+    let encryptor =
+        Encryptor::from_keys_with_fixed_iv(encryption_key, hmac_key.clone(), iv).unwrap();
+    let CipherText(cipher_text) = encryptor.cipher_text(&vec![0; len]).unwrap();
+    let HMAC(hmac) = HMAC::new(&header, &cipher_text, &hmac_key).unwrap();
+
+    let mut message = header.to_bytes();
+    message.extend(cipher_text);
+    message.extend(hmac);
+    message
+}
+
+/// The kind of the RNCryptor `Error` wrapped into an `io::Error`.
+pub fn error_kind(error: &io::Error) -> &ErrorKind {
+    &error
+        .get_ref()
+        .unwrap()
+        .downcast_ref::<Error>()
+        .unwrap()
+        .kind
+}
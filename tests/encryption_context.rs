@@ -0,0 +1,64 @@
+mod common;
+
+use common::encryptor;
+use quickcheck::QuickCheck;
+use rncryptor::v3::encryptor::Encryptor;
+
+fn update_in_chunks(encryptor: &Encryptor, plain_text: &[u8], chunk_size: usize) -> Vec<u8> {
+    let mut context = encryptor.context().unwrap();
+    let mut message = Vec::new();
+    for chunk in plain_text.chunks(chunk_size.max(1)) {
+        message.extend(context.update(chunk));
+    }
+    message.extend(context.finalize());
+    message
+}
+
+#[test]
+fn holds_back_incomplete_blocks() {
+    let mut context = encryptor().context().unwrap();
+
+    assert_eq!(context.update(&[0; 15]).len(), 18);
+    assert_eq!(context.update(&[0; 1]).len(), 16);
+    assert_eq!(context.update(&[0; 20]).len(), 16);
+    assert_eq!(context.finalize().len(), 16 + 32);
+}
+
+#[test]
+fn finalize_alone_matches_encrypt() {
+    let encryptor = encryptor();
+
+    assert_eq!(
+        encryptor.context().unwrap().finalize(),
+        encryptor.encrypt(&[]).unwrap()
+    )
+}
+
+#[test]
+fn appends_into_caller_buffers() {
+    let encryptor = encryptor();
+    let mut context = encryptor.context().unwrap();
+    let mut message = vec![];
+    context.update_into(b"some plain text", &mut message);
+    context.update_into(b" in a few chunks", &mut message);
+    context.finalize_into(&mut message);
+
+    assert_eq!(
+        message,
+        encryptor
+            .encrypt(b"some plain text in a few chunks")
+            .unwrap()
+    )
+}
+
+#[test]
+fn updates_match_encrypt() {
+    fn updated_equals_encrypted(message: Vec<u8>, chunk_size: u8) -> bool {
+        let encryptor = encryptor();
+        let updated = update_in_chunks(&encryptor, &message, chunk_size as usize);
+        updated == encryptor.encrypt(&message).unwrap()
+    }
+    QuickCheck::new()
+        .tests(100)
+        .quickcheck(updated_equals_encrypted as fn(Vec<u8>, u8) -> bool);
+}
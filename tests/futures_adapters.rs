@@ -0,0 +1,109 @@
+#![cfg(feature = "futures-io")]
+
+mod common;
+
+use common::{encryptor, error_kind, keys};
+use futures::{
+    executor::block_on,
+    io::{AsyncRead, AsyncReadExt, AsyncWriteExt},
+};
+use rncryptor::v3::{
+    self,
+    async_futures::{AsyncDecryptReader, AsyncEncryptWriter},
+    errors::ErrorKind,
+};
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// A reader handing out at most `chunk_size` bytes per read, and only every other poll.
+struct Stutter<'a> {
+    data: &'a [u8],
+    chunk_size: usize,
+    ready: bool,
+}
+
+impl<'a> AsyncRead for Stutter<'a> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.ready = !self.ready;
+        if !self.ready {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        let read = self.chunk_size.min(buf.len()).min(self.data.len());
+        buf[..read].copy_from_slice(&self.data[..read]);
+        self.data = &self.data[read..];
+        Poll::Ready(Ok(read))
+    }
+}
+
+#[test]
+fn writing_matches_encrypt() {
+    let encryptor = encryptor();
+    let plain_text: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+    let mut writer = AsyncEncryptWriter::new(&encryptor, Vec::new()).unwrap();
+    block_on(async {
+        for chunk in plain_text.chunks(37) {
+            writer.write_all(chunk).await.unwrap();
+        }
+        writer.close().await.unwrap();
+    });
+
+    assert_eq!(*writer.get_ref(), encryptor.encrypt(&plain_text).unwrap());
+}
+
+#[test]
+fn reads_key_based_messages() {
+    let plain_text: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+    let message = encryptor().encrypt(&plain_text).unwrap();
+    let (encryption_key, hmac_key) = keys();
+    let inner = Stutter {
+        data: &message,
+        chunk_size: 7,
+        ready: false,
+    };
+    let mut reader = AsyncDecryptReader::with_keys(encryption_key, hmac_key, inner);
+    let mut decrypted = Vec::new();
+    block_on(reader.read_to_end(&mut decrypted)).unwrap();
+
+    assert_eq!(decrypted, plain_text);
+}
+
+#[test]
+fn reads_password_based_messages() {
+    let message = v3::encrypt("thepassword", b"some secret data").unwrap();
+    let inner = Stutter {
+        data: &message,
+        chunk_size: 5,
+        ready: false,
+    };
+    let decrypted = block_on(async {
+        let mut reader = AsyncDecryptReader::from_password("thepassword", inner).await?;
+        let mut decrypted = Vec::new();
+        reader.read_to_end(&mut decrypted).await?;
+        Ok::<_, io::Error>(decrypted)
+    });
+
+    assert_eq!(decrypted.unwrap(), b"some secret data".to_vec());
+}
+
+#[test]
+fn rejects_tampered_messages() {
+    let mut message = encryptor().encrypt(b"some secret data").unwrap();
+    let last = message.len() - 1;
+    message[last] ^= 1;
+    let (encryption_key, hmac_key) = keys();
+    let mut reader = AsyncDecryptReader::with_keys(encryption_key, hmac_key, &message[..]);
+    let error = block_on(reader.read_to_end(&mut Vec::new())).unwrap_err();
+
+    assert!(matches!(
+        error_kind(&error),
+        ErrorKind::AuthenticationFailed
+    ));
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use common::{encryptor, keys, message};
+use quickcheck::QuickCheck;
+use rncryptor::v3::{self, header::Header, reader::DecryptingReader, types::*};
+use std::io::{Read, Write};
+
+/// Malformed messages which used to (or could) trip a panic somewhere in the decryption path.
+/// None of them is a valid message, so every entry must be rejected with an `Err`.
+const CORPUS: &[&str] = &[
+    "",
+    "03",
+    "0301",
+    "0300",
+    "0400",
+    "ff01",
+    "0302 00000000000000000000000000000000",
+    // Truncated headers.
+    "0301 0001020304050607 0102030405060708",
+    "0300 0001020304050607",
+    // Headers followed by less than an HMAC worth of data.
+    "0300 00000000000000000000000000000000 00",
+    "0301 0000000000000000 0000000000000000 00000000000000000000000000000000 0000",
+    // Header and HMAC, but no cipher text at all.
+    "0300 00000000000000000000000000000000 \
+     0000000000000000000000000000000000000000000000000000000000000000",
+    // Cipher text which isn't block-aligned.
+    "0300 00000000000000000000000000000000 000102030405060708090a0b0c0d0e0f 0001 \
+     0000000000000000000000000000000000000000000000000000000000000000",
+    "0301 0000000000000000 0000000000000000 00000000000000000000000000000000 \
+     000102030405060708090a0b0c0d0e0f 0001 \
+     0000000000000000000000000000000000000000000000000000000000000000",
+    // Key-based message handed to the password-based API and vice versa.
+    "0300 00000000000000000000000000000000 000102030405060708090a0b0c0d0e0f \
+     0000000000000000000000000000000000000000000000000000000000000000 \
+     0000000000000000000000000000000000000000000000000000000000000000",
+];
+
+fn decode_hex(hex: &str) -> Vec<u8> {
+    hex::decode(hex.replace(' ', "")).unwrap()
+}
+
+fn decrypt_with_keys(message: &[u8]) -> v3::errors::Result<Vec<u8>> {
+    let (encryption_key, hmac_key) = keys();
+    v3::decrypt_with_keys(encryption_key, hmac_key, &message.to_vec())
+}
+
+fn read_to_end(message: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut plain_text = Vec::new();
+    DecryptingReader::from_password("password", message)?.read_to_end(&mut plain_text)?;
+    Ok(plain_text)
+}
+
+fn valid_message() -> Vec<u8> {
+    message(b"a plain text spanning a couple of blocks")
+}
+
+#[test]
+fn corpus_is_rejected() {
+    for entry in CORPUS {
+        let message = decode_hex(entry);
+        assert!(v3::decrypt("password", &message).is_err(), "{}", entry);
+        assert!(decrypt_with_keys(&message).is_err(), "{}", entry);
+        assert!(read_to_end(&message).is_err(), "{}", entry);
+    }
+}
+
+#[test]
+fn truncated_messages_are_rejected() {
+    let message = valid_message();
+    for len in 0..message.len() {
+        assert!(decrypt_with_keys(&message[..len]).is_err(), "{}", len);
+    }
+}
+
+#[test]
+fn bit_flips_are_rejected() {
+    let message = valid_message();
+    for position in 0..message.len() {
+        let mut tampered = message.clone();
+        tampered[position] ^= 0x80;
+        assert!(decrypt_with_keys(&tampered).is_err(), "{}", position);
+    }
+}
+
+#[test]
+fn arbitrary_input_does_not_panic() {
+    fn returns(message: Vec<u8>) -> bool {
+        let _ = Header::parse(&message);
+        let _ = decrypt_with_keys(&message);
+        let mut keyed = vec![3, 0];
+        keyed.extend(&message);
+        decrypt_with_keys(&keyed).is_err()
+    }
+    QuickCheck::new()
+        .tests(1000)
+        .quickcheck(returns as fn(Vec<u8>) -> bool);
+}
+
+#[test]
+fn wrongly_sized_keys_and_ivs_are_rejected() {
+    assert!(EncryptionKey::try_from(vec![1; 31]).is_err());
+    assert!(HMACKey::try_from(&[][..]).is_err());
+    assert!(IV::try_from(vec![3; 4]).is_err());
+    assert!(IV::from_hex("0405060708090a0b0c0d0e0f000102").is_err());
+    assert!(Salt::from_hex("not hex at all!!").is_err());
+}
+
+#[test]
+fn unaligned_unpadded_input_is_rejected() {
+    let encryptor = encryptor();
+
+    assert!(encryptor.cipher_text(&[0; 15]).is_err());
+    assert!(encryptor.cipher_text(&[0; 32]).is_ok());
+}
+
+#[test]
+fn unfinished_writer_output_is_rejected() {
+    let encryptor = encryptor();
+    let mut message = Vec::new();
+    let mut writer = encryptor.writer(&mut message).unwrap();
+    writer.write_all(&[0; 100]).unwrap();
+    drop(writer);
+
+    assert!(decrypt_with_keys(&message).is_err());
+}
@@ -0,0 +1,66 @@
+use rncryptor::v3::{
+    self,
+    errors::ErrorKind,
+    header::Header,
+    types::{Salt, IV},
+};
+
+fn decode_hex(hex: &str) -> Vec<u8> {
+    hex::decode(hex.replace(' ', "")).unwrap()
+}
+
+#[test]
+fn parses_password_header() {
+    let bytes =
+        decode_hex("0301 0203040506070001 0304050607080102 0405060708090a0b0c0d0e0f00010203 ffff");
+    let header = Header::parse(&bytes).unwrap();
+    let expected = Header::Password {
+        encryption_salt: Salt::from_hex("0203040506070001").unwrap(),
+        hmac_salt: Salt::from_hex("0304050607080102").unwrap(),
+        iv: IV::from_hex("0405060708090a0b0c0d0e0f00010203").unwrap(),
+    };
+
+    assert_eq!(header, expected);
+    assert_eq!(header.to_bytes(), bytes[..34].to_vec())
+}
+
+#[test]
+fn parses_key_header() {
+    let bytes = decode_hex("0300 0405060708090a0b0c0d0e0f00010203");
+    let header = Header::parse(&bytes).unwrap();
+
+    assert_eq!(
+        header,
+        Header::Key {
+            iv: IV::try_from(&bytes[2..]).unwrap()
+        }
+    );
+    assert_eq!(header.to_bytes(), bytes)
+}
+
+#[test]
+fn rejects_unknown_version() {
+    let error = Header::parse(&[2, 1]).unwrap_err();
+    assert!(matches!(error.kind, ErrorKind::UnsupportedVersion(2)))
+}
+
+#[test]
+fn rejects_unknown_options() {
+    let error = Header::parse(&[3, 0x81]).unwrap_err();
+    assert!(matches!(error.kind, ErrorKind::UnknownOptions(0x81)))
+}
+
+#[test]
+fn rejects_truncated_header() {
+    let error = Header::parse(&[3, 1, 0, 0]).unwrap_err();
+    assert!(matches!(error.kind, ErrorKind::TruncatedHeader(4)))
+}
+
+#[test]
+fn rejects_message_with_tampered_options() {
+    let mut message = v3::encrypt("password", b"secret").unwrap();
+    message[1] = 0x03;
+
+    let error = v3::decrypt("password", &message).unwrap_err();
+    assert!(matches!(error.kind, ErrorKind::UnknownOptions(0x03)))
+}
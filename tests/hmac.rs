@@ -0,0 +1,57 @@
+use rncryptor::v3::{
+    decryptor::Decryptor, encryptor::Encryptor, errors::ErrorKind, header::Header, types::*,
+};
+
+fn computed_hmac() -> HMAC {
+    let header = Header::Key {
+        iv: IV::from([3; 16]),
+    };
+    HMAC::new(&header, &[0; 16], &HMACKey::from([2; 32])).unwrap()
+}
+
+#[test]
+fn equal_hmacs_compare_equal() {
+    assert!(computed_hmac().is_equal_in_consistent_time_to(&computed_hmac()))
+}
+
+#[test]
+fn different_hmacs_compare_different() {
+    let HMAC(mut other) = computed_hmac();
+    other[31] ^= 1;
+
+    assert!(!computed_hmac().is_equal_in_consistent_time_to(&HMAC(other)))
+}
+
+#[test]
+fn rejects_truncated_hmac() {
+    let HMAC(hmac) = computed_hmac();
+    let error = HMAC::from_slice(&hmac[..16]).unwrap_err();
+
+    assert!(matches!(error.kind, ErrorKind::HMACNotFound))
+}
+
+#[test]
+fn rejects_empty_hmac() {
+    let error = HMAC::from_slice(&[]).unwrap_err();
+
+    assert!(matches!(error.kind, ErrorKind::HMACNotFound))
+}
+
+#[test]
+fn decryptor_rejects_message_without_room_for_the_hmac() {
+    let encryption_key = EncryptionKey::from([1; 32]);
+    let hmac_key = HMACKey::from([2; 32]);
+    let message = Encryptor::from_keys_with_fixed_iv(
+        encryption_key.clone(),
+        hmac_key.clone(),
+        IV::from([3; 16]),
+    )
+    .and_then(|e| e.encrypt(b"secret"))
+    .unwrap();
+    let decryptor = Decryptor::from_keys(encryption_key, hmac_key, &message).unwrap();
+
+    for len in [0, 18, 18 + 16, 18 + 31] {
+        let error = decryptor.decrypt(&message[..len]).unwrap_err();
+        assert!(matches!(error.kind, ErrorKind::HMACNotFound))
+    }
+}
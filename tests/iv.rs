@@ -12,3 +12,20 @@ fn iv_is_16_elem_long() {
         Ok(iv) => assert_eq!(iv.as_slice().len(), 16),
     }
 }
+
+#[test]
+fn iv_can_be_parsed_from_hex() {
+    let iv = IV::from_hex("00010203 04050607 08090a0b 0c0d0e0f").unwrap();
+    assert_eq!(iv.as_ref(), (0..16).collect::<Vec<u8>>().as_slice())
+}
+
+#[test]
+fn iv_of_the_wrong_size_is_rejected() {
+    match IV::try_from(vec![0; 15]) {
+        Err(e) => assert!(matches!(
+            e.kind,
+            rncryptor::v3::errors::ErrorKind::WrongInputSize(15)
+        )),
+        Ok(iv) => panic!("{:?}", iv),
+    }
+}
@@ -0,0 +1,63 @@
+use rncryptor::v3::{decryptor::Decryptor, encryptor::Encryptor, types::*};
+use std::num::NonZeroU32;
+
+fn fast_params() -> KdfParams {
+    KdfParams::non_standard(NonZeroU32::new(1).unwrap(), Prf::HmacSha256)
+}
+
+fn encrypt_with(params: &KdfParams, plain_text: &[u8]) -> Vec<u8> {
+    Encryptor::from_password_with_params(
+        "thepassword",
+        Salt([1; 8]),
+        Salt([2; 8]),
+        IV::from([3; 16]),
+        params,
+    )
+    .and_then(|e| e.encrypt(plain_text))
+    .unwrap()
+}
+
+#[test]
+fn default_params_are_the_spec_ones() {
+    let params = KdfParams::default();
+
+    assert!(params.is_standard());
+    assert_eq!(params.iterations().get(), 10_000);
+    assert_eq!(params.prf(), Prf::HmacSha1);
+}
+
+#[test]
+fn other_params_are_non_standard() {
+    let params = KdfParams::non_standard(NonZeroU32::new(20_000).unwrap(), Prf::HmacSha1);
+
+    assert!(!params.is_standard());
+    assert!(!fast_params().is_standard());
+}
+
+#[test]
+fn explicit_default_params_match_the_implicit_ones() {
+    let salt = Salt([1; 8]);
+
+    assert_eq!(
+        HMACKey::new_with_params(&salt, b"secret", &KdfParams::default()),
+        HMACKey::new(&salt, b"secret")
+    );
+}
+
+#[test]
+fn non_standard_params_roundtrip() {
+    let message = encrypt_with(&fast_params(), b"secret");
+    let decrypted = Decryptor::from_with_params("thepassword", &message, &fast_params())
+        .and_then(|d| d.decrypt(&message))
+        .unwrap();
+
+    assert_eq!(decrypted, b"secret".to_vec());
+}
+
+#[test]
+fn mismatched_params_fail_to_decrypt() {
+    let message = encrypt_with(&fast_params(), b"secret");
+    let result = Decryptor::from("thepassword", &message).and_then(|d| d.decrypt(&message));
+
+    assert!(result.is_err());
+}
@@ -0,0 +1,71 @@
+use rncryptor::v3::{self, decryptor::Decryptor, encryptor::Encryptor, types::*};
+
+struct TestVector {
+    encryption_key: &'static str,
+    hmac_key: &'static str,
+    plain_text: &'static str,
+    cipher_text: &'static str,
+}
+
+fn decode_hex(hex: &str) -> Vec<u8> {
+    hex::decode(hex.replace(' ', "")).unwrap()
+}
+
+fn test_vector(vector: TestVector) {
+    let encryption_key = EncryptionKey::from_hex(vector.encryption_key).unwrap();
+    let hmac_key = HMACKey::from_hex(vector.hmac_key).unwrap();
+    let plain_text = decode_hex(vector.plain_text);
+    let ciphertext = decode_hex(vector.cipher_text);
+    match v3::decrypt_with_keys(encryption_key, hmac_key, &ciphertext) {
+        Err(e) => panic!("{:?}", e),
+        Ok(decrypted) => assert_eq!(decrypted, plain_text),
+    }
+}
+
+#[test]
+fn all_fields_empty_or_zero() {
+    test_vector(TestVector {
+        encryption_key: "0000000000000000000000000000000000000000000000000000000000000000",
+        hmac_key: "0000000000000000000000000000000000000000000000000000000000000000",
+        plain_text: "",
+        cipher_text: "03000000 00000000 00000000 00000000 00001f78 8fe6d86c 31754969 7fbf0c07 \
+                      fa436384 ac0ef35b 860b2ddb 2aba2fff 816b1fb3 a9c180f7 b43650ae c0d2b5f8 8e33",
+    })
+}
+
+#[test]
+fn one_byte() {
+    test_vector(TestVector {
+        encryption_key: "000102030405060708090a0b0c0d0e0f000102030405060708090a0b0c0d0e0f",
+        hmac_key: "0102030405060708090a0b0c0d0e0f000102030405060708090a0b0c0d0e0f00",
+        plain_text: "01",
+        cipher_text: "03000203 04050607 08090a0b 0c0d0e0f 0001981b 22e7a644 8118d695 bd654f72 \
+                      e9d6ed75 ec14ae2a a067eed2 a98a56e0 993dfe22 ab5887b3 f6e3cdd4 0767f519 5eb5",
+    })
+}
+
+#[test]
+fn more_than_one_block() {
+    test_vector(TestVector {
+        encryption_key: "02030405060708090a0b0c0d0e0f000102030405060708090a0b0c0d0e0f0001",
+        hmac_key: "030405060708090a0b0c0d0e0f000102030405060708090a0b0c0d0e0f000102",
+        plain_text: "000102030405060708090a0b0c0d0e0f 000102030405060708",
+        cipher_text: "03000405 06070809 0a0b0c0d 0e0f0001 02034c9b 98b425f1 d732644c b311278d \
+                      858e3d18 2a0789b8 6af7f741 34b6a27e 9d938617 741c0fb8 aaf094b3 b5b26f50 \
+                      5da7bf19 13f6c17e 70273977 ae51323b 6f09",
+    })
+}
+
+#[test]
+fn wrong_hmac_key_fails() {
+    let encryption_key = EncryptionKey::from([1; 32]);
+    let iv = IV::from([3; 16]);
+    let message =
+        Encryptor::from_keys_with_fixed_iv(encryption_key.clone(), HMACKey::from([2; 32]), iv)
+            .and_then(|e| e.encrypt(b"secret"))
+            .unwrap();
+    let result = Decryptor::from_keys(encryption_key, HMACKey::from([4; 32]), &message)
+        .and_then(|d| d.decrypt(&message));
+
+    assert!(result.is_err())
+}
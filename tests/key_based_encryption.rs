@@ -13,13 +13,13 @@ fn decode_hex(hex: &str) -> Vec<u8> {
 }
 
 fn test_vector(vector: TestVector) {
-    let encryption_key = EncryptionKey::from(decode_hex(vector.encryption_key));
-    let hmac_key = HMACKey::from(decode_hex(vector.hmac_key));
-    let iv = IV::from(decode_hex(vector.iv));
+    let encryption_key = EncryptionKey::from_hex(vector.encryption_key).unwrap();
+    let hmac_key = HMACKey::from_hex(vector.hmac_key).unwrap();
+    let iv = IV::from_hex(vector.iv).unwrap();
     let plain_text = decode_hex(vector.plain_text);
     let ciphertext = decode_hex(vector.cipher_text);
-    let result =
-        Encryptor::from_keys(encryption_key, hmac_key, iv).and_then(|e| e.encrypt(&plain_text));
+    let result = Encryptor::from_keys_with_fixed_iv(encryption_key, hmac_key, iv)
+        .and_then(|e| e.encrypt(&plain_text));
     match result {
         Err(e) => panic!("{:?}", e),
         Ok(encrypted) => assert_eq!(*encrypted.as_slice(), *ciphertext.as_slice()),
@@ -0,0 +1,178 @@
+//! The v0 and v1 messages are assembled by hand out of independent primitives (the `ctr` and
+//! `cbc` crates for AES, the `hmac` crate for HMAC-SHA256 over the cipher text only).
+//!
+//! The known-answer vectors don't go through this crate at all. The v1 one is the "More than one
+//! block" v3 spec vector with its version byte set to 1 (the cipher text is the spec's,
+//! untouched) and an HMAC over the cipher text alone, computed with Python's `hashlib` and
+//! `hmac`. The v0 one shares its salts, `IV` and plain text, encrypted in CTR mode with Python's
+//! `cryptography`.
+use aes::cipher::{KeyIvInit, StreamCipher};
+use hmac::{Hmac, Mac};
+use rncryptor::{
+    legacy::{self, decryptor::Decryptor},
+    v3::{encryptor::Encryptor, errors::ErrorKind, types::*},
+    Version,
+};
+use sha2::Sha256;
+
+type Aes256Ctr = ctr::Ctr128BE<aes::Aes256>;
+
+fn decode_hex(hex: &str) -> Vec<u8> {
+    hex::decode(hex.replace(' ', "")).unwrap()
+}
+
+fn cbc_cipher_text(encryption_key: EncryptionKey, iv: IV, plain_text: &[u8]) -> Vec<u8> {
+    let CipherText(cipher_text) =
+        Encryptor::from_keys_with_fixed_iv(encryption_key, HMACKey::from([0; 32]), iv)
+            .and_then(|e| e.cipher_text_pkcs7(plain_text))
+            .unwrap();
+    cipher_text
+}
+
+fn ctr_cipher_text(encryption_key: &EncryptionKey, iv: &IV, plain_text: &[u8]) -> Vec<u8> {
+    let mut cipher_text = plain_text.to_vec();
+    Aes256Ctr::new(encryption_key.as_slice().into(), iv.as_slice().into())
+        .apply_keystream(&mut cipher_text);
+    cipher_text
+}
+
+fn legacy_message(header: Vec<u8>, cipher_text: Vec<u8>, hmac_key: &HMACKey) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(hmac_key.as_slice()).unwrap();
+    mac.update(&cipher_text);
+    let mut message = header;
+    message.extend(cipher_text);
+    message.extend(mac.finalize().into_bytes());
+    message
+}
+
+fn key_header(version: u8, iv: &IV) -> Vec<u8> {
+    let mut header = vec![version, 0];
+    header.extend(iv.as_slice());
+    header
+}
+
+const PLAIN_TEXT: &[u8] = b"an old message, from the early days of the format";
+
+#[test]
+fn decrypts_key_based_v1_message() {
+    let iv = IV::from([3; 16]);
+    let cipher_text = cbc_cipher_text(EncryptionKey::from([1; 32]), iv.clone(), PLAIN_TEXT);
+    let message = legacy_message(key_header(1, &iv), cipher_text, &HMACKey::from([2; 32]));
+    let decrypted = legacy::decrypt_with_keys(
+        EncryptionKey::from([1; 32]),
+        HMACKey::from([2; 32]),
+        &message,
+    );
+
+    assert_eq!(decrypted.unwrap(), PLAIN_TEXT.to_vec());
+}
+
+#[test]
+fn decrypts_key_based_v0_message() {
+    let iv = IV::from([0xff; 16]);
+    let cipher_text = ctr_cipher_text(&EncryptionKey::from([1; 32]), &iv, PLAIN_TEXT);
+    let message = legacy_message(key_header(0, &iv), cipher_text, &HMACKey::from([2; 32]));
+    let decryptor = Decryptor::from_keys(
+        EncryptionKey::from([1; 32]),
+        HMACKey::from([2; 32]),
+        &message,
+    )
+    .unwrap();
+
+    assert_eq!(decryptor.version, Version::V0);
+    assert_eq!(decryptor.decrypt(&message).unwrap(), PLAIN_TEXT.to_vec());
+}
+
+#[test]
+fn decrypts_password_based_v1_message() {
+    let (encryption_salt, hmac_salt, iv) = (Salt([1; 8]), Salt([2; 8]), IV::from([3; 16]));
+    let mut header = vec![1, 1];
+    header.extend(encryption_salt.as_slice());
+    header.extend(hmac_salt.as_slice());
+    header.extend(iv.as_slice());
+    let cipher_text = cbc_cipher_text(
+        EncryptionKey::new(&encryption_salt, b"thepassword"),
+        iv,
+        PLAIN_TEXT,
+    );
+    let message = legacy_message(
+        header,
+        cipher_text,
+        &HMACKey::new(&hmac_salt, b"thepassword"),
+    );
+
+    assert_eq!(
+        legacy::decrypt("thepassword", &message).unwrap(),
+        PLAIN_TEXT.to_vec()
+    );
+}
+
+#[test]
+fn decrypts_known_answer_v1_vector() {
+    let message = decode_hex(
+        "01010203 04050607 00010304 05060708 01020405 06070809 0a0b0c0d 0e0f0001 \
+         0203e01b bda5df2c a8adace3 8f6c588d 291e5d35 9b8965c7 9ddcfb43 8b53b5f1 \
+         e3a77e64 1f241761 8039eb4f 59017e60 e89f",
+    );
+
+    assert_eq!(
+        legacy::decrypt("thepassword", &message).unwrap(),
+        decode_hex("0123456789abcdef 01234567")
+    );
+}
+
+#[test]
+fn decrypts_known_answer_v0_vector() {
+    let message = decode_hex(
+        "00010203 04050607 00010304 05060708 01020405 06070809 0a0b0c0d 0e0f0001 \
+         020398a9 b1c7f642 b4c7299f d68aeb1b 53d885ce 87681e5f f7f16f1d 35ef5b16 \
+         7ac126f2 369f5f6d 3ee45b81 7380",
+    );
+
+    assert_eq!(
+        legacy::decrypt("thepassword", &message).unwrap(),
+        decode_hex("0123456789abcdef 01234567")
+    );
+}
+
+#[test]
+fn rejects_hmac_over_the_header() {
+    // The known-answer v1 vector, but authenticated the v2 way (header and cipher text).
+    let message = decode_hex(
+        "01010203 04050607 00010304 05060708 01020405 06070809 0a0b0c0d 0e0f0001 \
+         0203e01b bda5df2c a8adace3 8f6c588d 291ea4f2 f285c6f3 436f04fa 2b5fd39a \
+         c344c08c 975476e9 fd39e842 5ea647c9 ca33",
+    );
+
+    let error = legacy::decrypt("thepassword", &message).unwrap_err();
+    assert!(matches!(error.kind, ErrorKind::AuthenticationFailed));
+}
+
+#[test]
+fn tampered_cipher_text_is_rejected() {
+    let iv = IV::from([3; 16]);
+    let cipher_text = cbc_cipher_text(EncryptionKey::from([1; 32]), iv.clone(), PLAIN_TEXT);
+    let message = legacy_message(key_header(1, &iv), cipher_text, &HMACKey::from([2; 32]));
+    let mut tampered_cipher_text = message.clone();
+    tampered_cipher_text[20] ^= 1;
+
+    let error = legacy::decrypt_with_keys(
+        EncryptionKey::from([1; 32]),
+        HMACKey::from([2; 32]),
+        &tampered_cipher_text,
+    )
+    .unwrap_err();
+    assert!(matches!(error.kind, ErrorKind::AuthenticationFailed));
+}
+
+#[test]
+fn rejects_newer_versions() {
+    let error = legacy::decrypt_with_keys(
+        EncryptionKey::from([1; 32]),
+        HMACKey::from([2; 32]),
+        &vec![2, 0],
+    )
+    .unwrap_err();
+
+    assert!(matches!(error.kind, ErrorKind::UnsupportedVersion(2)));
+}
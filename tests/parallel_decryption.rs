@@ -0,0 +1,57 @@
+//! Large enough messages are decrypted on the rayon thread pool: these check that the result
+//! can't be told apart from the sequential decryption.
+#![cfg(feature = "parallel")]
+
+mod common;
+
+use common::{authenticated_message_with_bad_padding, decryptor, message, plain_text};
+use rncryptor::v3::errors::ErrorKind;
+
+#[test]
+fn decrypts_large_messages() {
+    let sizes = [
+        256 * 1024 - 16,
+        256 * 1024,
+        256 * 1024 + 15,
+        64 * 1024 * 7 + 1,
+        3 * 1024 * 1024 + 5,
+    ];
+    for len in sizes {
+        let plain_text = plain_text(len);
+        let message = message(&plain_text);
+
+        assert_eq!(
+            decryptor(&message).decrypt(&message).unwrap(),
+            plain_text,
+            "{}",
+            len
+        );
+    }
+}
+
+#[test]
+fn tampering_is_opaque() {
+    let mut message = message(&plain_text(1024 * 1024));
+    message[100_000] ^= 1;
+    let error = decryptor(&message).decrypt(&message).unwrap_err();
+
+    assert!(matches!(error.kind, ErrorKind::AuthenticationFailed));
+}
+
+#[test]
+fn bad_padding_is_reported_after_authentication() {
+    let message = authenticated_message_with_bad_padding(512 * 1024);
+
+    let decryptor = decryptor(&message);
+    assert!(matches!(
+        decryptor.decrypt(&message).unwrap_err().kind,
+        ErrorKind::AuthenticationFailed
+    ));
+    assert!(matches!(
+        decryptor
+            .decrypt_with_diagnostics(&message)
+            .unwrap_err()
+            .kind,
+        ErrorKind::UnpadError
+    ));
+}
@@ -17,9 +17,9 @@ fn decode_hex(hex: &str) -> Vec<u8> {
 }
 
 fn test_vector(vector: TestVector) {
-    let encryption_salt = Salt(decode_hex(vector.encryption_salt));
-    let hmac_salt = Salt(decode_hex(vector.hmac_salt));
-    let iv = IV::from(decode_hex(vector.iv));
+    let encryption_salt = Salt::from_hex(vector.encryption_salt).unwrap();
+    let hmac_salt = Salt::from_hex(vector.hmac_salt).unwrap();
+    let iv = IV::from_hex(vector.iv).unwrap();
     let plain_text = decode_hex(vector.plain_text);
     let ciphertext = decode_hex(vector.cipher_text);
     let result = Encryptor::from_password(vector.password, encryption_salt, hmac_salt, iv)
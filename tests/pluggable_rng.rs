@@ -0,0 +1,78 @@
+use rand::{rngs::StdRng, SeedableRng};
+use rncryptor::v3::{
+    self,
+    encryptor::{Encryptor, ReusableEncryptor},
+    types::*,
+};
+use std::io::Write;
+
+#[test]
+fn seeded_rng_makes_encryption_reproducible() {
+    let first = v3::encrypt_with_rng(&mut StdRng::seed_from_u64(7), "thepassword", b"secret");
+    let second = v3::encrypt_with_rng(&mut StdRng::seed_from_u64(7), "thepassword", b"secret");
+
+    assert_eq!(first.unwrap(), second.unwrap())
+}
+
+#[test]
+fn encrypt_with_rng_round_trips() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let message = v3::encrypt_with_rng(&mut rng, "thepassword", b"secret").unwrap();
+
+    assert_eq!(v3::decrypt("thepassword", &message).unwrap(), b"secret")
+}
+
+#[test]
+fn salts_and_iv_come_from_the_rng() {
+    let mut rng = StdRng::seed_from_u64(42);
+    let message = v3::encrypt_with_rng(&mut rng, "thepassword", b"secret").unwrap();
+
+    let mut rng = StdRng::seed_from_u64(42);
+    let encryption_salt = Salt::new_with_rng(&mut rng).unwrap();
+    let hmac_salt = Salt::new_with_rng(&mut rng).unwrap();
+    let iv = IV::new_with_rng(&mut rng).unwrap();
+    let expected = Encryptor::from_password("thepassword", encryption_salt, hmac_salt, iv)
+        .and_then(|e| e.encrypt(b"secret"))
+        .unwrap();
+
+    assert_eq!(message, expected)
+}
+
+#[test]
+fn reusable_encryptor_draws_from_the_rng() {
+    let encryption_key = EncryptionKey::from([1; 32]);
+    let hmac_key = HMACKey::from([2; 32]);
+    let encryptor = ReusableEncryptor::from_keys(encryption_key, hmac_key);
+
+    let mut rng = StdRng::seed_from_u64(3);
+    let first = encryptor.encrypt_with_rng(&mut rng, b"secret").unwrap();
+    let second = encryptor.encrypt_with_rng(&mut rng, b"secret").unwrap();
+    assert_ne!(first, second);
+
+    let again = encryptor
+        .encrypt_with_rng(&mut StdRng::seed_from_u64(3), b"secret")
+        .unwrap();
+    assert_eq!(first, again)
+}
+
+#[test]
+fn streaming_apis_draw_from_the_rng() {
+    let encryptor =
+        ReusableEncryptor::from_keys(EncryptionKey::from([1; 32]), HMACKey::from([2; 32]));
+    let expected = encryptor
+        .encrypt_with_rng(&mut StdRng::seed_from_u64(5), b"secret")
+        .unwrap();
+
+    let mut context = encryptor
+        .context_with_rng(&mut StdRng::seed_from_u64(5))
+        .unwrap();
+    let mut pushed = context.update(b"secret");
+    pushed.extend(context.finalize());
+    assert_eq!(pushed, expected);
+
+    let mut writer = encryptor
+        .writer_with_rng(&mut StdRng::seed_from_u64(5), Vec::new())
+        .unwrap();
+    writer.write_all(b"secret").unwrap();
+    assert_eq!(writer.finish().unwrap(), expected);
+}
@@ -0,0 +1,103 @@
+mod common;
+
+use common::keys;
+use rncryptor::v3::{decryptor::Decryptor, encryptor::ReusableEncryptor, types::*};
+use std::{io::Write, num::NonZeroU32};
+
+fn fast_params() -> KdfParams {
+    KdfParams::non_standard(NonZeroU32::new(2).unwrap(), Prf::HmacSha1)
+}
+
+#[test]
+fn is_send_and_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<ReusableEncryptor>();
+}
+
+#[test]
+fn key_based_messages_get_fresh_ivs() {
+    let (encryption_key, hmac_key) = keys();
+    let encryptor = ReusableEncryptor::from_keys(encryption_key.clone(), hmac_key.clone());
+    let first = encryptor.encrypt(b"same plain text").unwrap();
+    let second = encryptor.encrypt(b"same plain text").unwrap();
+
+    assert_ne!(first[2..18], second[2..18]);
+    for message in [first, second] {
+        let plain_text = Decryptor::from_keys(encryption_key.clone(), hmac_key.clone(), &message)
+            .and_then(|d| d.decrypt(&message))
+            .unwrap();
+        assert_eq!(plain_text, b"same plain text")
+    }
+}
+
+#[test]
+fn fixed_salts_only_change_the_iv() {
+    let salts = (Salt([1; 8]), Salt([2; 8]));
+    let encryptor =
+        ReusableEncryptor::from_password_with_fixed_salts("thepassword", salts.0, salts.1).unwrap();
+    let first = encryptor.encrypt(b"secret").unwrap();
+    let second = encryptor.encrypt(b"secret").unwrap();
+
+    assert_eq!(first[..18], second[..18]);
+    assert_ne!(first[18..34], second[18..34]);
+    let plain_text = Decryptor::from("thepassword", &second)
+        .and_then(|d| d.decrypt(&second))
+        .unwrap();
+    assert_eq!(plain_text, b"secret")
+}
+
+#[test]
+fn password_based_messages_get_fresh_salts() {
+    let params = fast_params();
+    let encryptor = ReusableEncryptor::from_password_with_params("thepassword", &params).unwrap();
+    let first = encryptor.encrypt(b"secret").unwrap();
+    let second = encryptor.encrypt(b"secret").unwrap();
+
+    assert_ne!(first[2..18], second[2..18]);
+    for message in [first, second] {
+        let plain_text = Decryptor::from_with_params("thepassword", &message, &params)
+            .and_then(|d| d.decrypt(&message))
+            .unwrap();
+        assert_eq!(plain_text, b"secret")
+    }
+}
+
+#[test]
+fn fixed_salts_honour_the_kdf_params() {
+    let params = fast_params();
+    let encryptor = ReusableEncryptor::from_password_with_fixed_salts_and_params(
+        "thepassword",
+        Salt([1; 8]),
+        Salt([2; 8]),
+        &params,
+    )
+    .unwrap();
+    let message = encryptor.encrypt(b"secret").unwrap();
+
+    let plain_text = Decryptor::from_with_params("thepassword", &message, &params)
+        .and_then(|d| d.decrypt(&message))
+        .unwrap();
+    assert_eq!(plain_text, b"secret")
+}
+
+#[test]
+fn rejects_empty_password() {
+    assert!(ReusableEncryptor::from_password("").is_err());
+    assert!(
+        ReusableEncryptor::from_password_with_fixed_salts("", Salt([1; 8]), Salt([2; 8])).is_err()
+    );
+}
+
+#[test]
+fn writer_streams_a_fresh_message() {
+    let (encryption_key, hmac_key) = keys();
+    let encryptor = ReusableEncryptor::from_keys(encryption_key.clone(), hmac_key.clone());
+    let mut writer = encryptor.writer(Vec::new()).unwrap();
+    writer.write_all(b"streamed").unwrap();
+    let message = writer.finish().unwrap();
+
+    let plain_text = Decryptor::from_keys(encryption_key, hmac_key, &message)
+        .and_then(|d| d.decrypt(&message))
+        .unwrap();
+    assert_eq!(plain_text, b"streamed")
+}
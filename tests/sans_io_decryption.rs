@@ -0,0 +1,132 @@
+mod common;
+
+use common::{keys, message};
+use quickcheck::QuickCheck;
+use rncryptor::v3::{
+    context::{DecryptionContext, Event},
+    decryptor::Decryptor,
+    errors::{Error, ErrorKind},
+};
+
+fn decode_hex(hex: &str) -> Vec<u8> {
+    hex::decode(hex.replace(' ', "")).unwrap()
+}
+
+/// Feeds `message` in chunks of `chunk_size`, collecting the plain text until the end.
+fn run(
+    mut context: DecryptionContext,
+    message: &[u8],
+    chunk_size: usize,
+) -> Result<Vec<u8>, Error> {
+    let mut plain_text = Vec::new();
+    for chunk in message.chunks(chunk_size.max(1)) {
+        match context.feed(chunk) {
+            Event::PlainText(decrypted) => plain_text.extend(decrypted.iter()),
+            Event::Failed(error) => return Err(error),
+            Event::Authenticated(_) => unreachable!(),
+        }
+    }
+    match context.finish() {
+        Event::Authenticated(decrypted) => plain_text.extend(decrypted.iter()),
+        Event::Failed(error) => return Err(error),
+        Event::PlainText(_) => unreachable!(),
+    }
+    Ok(plain_text)
+}
+
+fn with_keys() -> DecryptionContext {
+    let (encryption_key, hmac_key) = keys();
+    DecryptionContext::with_keys(encryption_key, hmac_key)
+}
+
+#[test]
+fn decrypts_password_test_vector() {
+    let message = decode_hex(
+        "03010203 04050607 00010304 05060708 01020405 06070809 0a0b0c0d 0e0f0001 \
+         0203e01b bda5df2c a8adace3 8f6c588d 291e03f9 51b78d34 17bc2816 581dc6b7 \
+         67f1a2e5 7597512b 18e1638f 21235fa5 928c",
+    );
+    let plain_text = run(DecryptionContext::with_password("thepassword"), &message, 1);
+
+    assert_eq!(plain_text.unwrap(), decode_hex("0123456789abcdef 01234567"))
+}
+
+#[test]
+fn withholds_the_last_block_until_authenticated() {
+    let message = message(&[7; 40]);
+    let mut context = with_keys();
+
+    match context.feed(&message) {
+        Event::PlainText(plain_text) => assert_eq!(plain_text.len(), 32),
+        event => panic!("{:?}", event),
+    }
+    match context.finish() {
+        Event::Authenticated(plain_text) => assert_eq!(plain_text.len(), 8),
+        event => panic!("{:?}", event),
+    }
+}
+
+#[test]
+fn rejects_tampered_hmac() {
+    let mut message = message(b"some secret data");
+    let last = message.len() - 1;
+    message[last] ^= 1;
+
+    let error = run(with_keys(), &message, 7).unwrap_err();
+    assert!(matches!(error.kind, ErrorKind::AuthenticationFailed))
+}
+
+#[test]
+fn rejects_truncated_messages() {
+    let message = message(b"some secret data");
+    for len in 0..message.len() {
+        assert!(run(with_keys(), &message[..len], 3).is_err(), "{}", len);
+    }
+}
+
+#[test]
+fn rejects_unknown_versions_early() {
+    let mut context = with_keys();
+
+    assert!(matches!(
+        context.feed(&[4, 0]),
+        Event::Failed(Error {
+            kind: ErrorKind::UnsupportedVersion(4),
+            ..
+        })
+    ));
+    assert!(matches!(context.feed(&[0; 64]), Event::Failed(_)));
+}
+
+#[test]
+fn rejects_password_messages_given_keys() {
+    let message =
+        decode_hex("0301 0203040506070001 0304050607080102 0405060708090a0b0c0d0e0f00010203");
+    let error = run(with_keys(), &message, 64).unwrap_err();
+
+    assert!(matches!(error.kind, ErrorKind::UnexpectedOptions(1)))
+}
+
+#[test]
+fn resumes_after_a_decryptor() {
+    let (encryption_key, hmac_key) = keys();
+    let message = message(b"some secret data");
+    let decryptor = Decryptor::from_keys(encryption_key, hmac_key, &message).unwrap();
+    let context = DecryptionContext::from_decryptor(&decryptor).unwrap();
+
+    assert_eq!(
+        run(context, &message[18..], 5).unwrap(),
+        b"some secret data".to_vec()
+    )
+}
+
+#[test]
+fn feeding_matches_decrypt() {
+    fn fed_equals_plain_text(plain_text: Vec<u8>, chunk_size: u8) -> bool {
+        let message = message(&plain_text);
+        run(with_keys(), &message, chunk_size as usize).unwrap() == plain_text
+    }
+    QuickCheck::new()
+        .tests(100)
+        .quickcheck(fed_equals_plain_text as fn(Vec<u8>, u8) -> bool);
+}
@@ -0,0 +1,40 @@
+use rncryptor::v3::{encryptor::Encryptor, types::*};
+use zeroize::Zeroizing;
+
+#[test]
+fn keys_are_redacted_in_debug_output() {
+    let encryption_key = EncryptionKey::from([0xab; 32]);
+    let hmac_key = HMACKey::from([0xcd; 32]);
+
+    assert_eq!(format!("{:?}", encryption_key), "EncryptionKey(<redacted>)");
+    assert_eq!(format!("{:?}", hmac_key), "HMACKey(<redacted>)");
+}
+
+#[test]
+fn encryptor_debug_output_does_not_leak_keys() {
+    let encryptor = Encryptor::from_keys_with_fixed_iv(
+        EncryptionKey::from([0xab; 32]),
+        HMACKey::from([0xcd; 32]),
+        IV::from([1; 16]),
+    )
+    .unwrap();
+    let debug = format!("{:?}", encryptor);
+
+    assert!(debug.contains("<redacted>"));
+    assert!(!debug.contains("171"));
+    assert!(!debug.contains("205"));
+}
+
+#[test]
+fn passwords_are_redacted_in_debug_output() {
+    let password = Password::from(Zeroizing::new("hunter2".to_owned()));
+
+    assert_eq!(format!("{:?}", password), "Password(<redacted>)");
+    assert_eq!(password.as_bytes(), b"hunter2");
+}
+
+#[test]
+fn hmac_keys_compare_by_value() {
+    assert_eq!(HMACKey::from([1; 32]), HMACKey::from([1; 32]));
+    assert_ne!(HMACKey::from([1; 32]), HMACKey::from([2; 32]));
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use common::{decryptor, keys, message, plain_text};
+use quickcheck::QuickCheck;
+use rncryptor::v3::{
+    encryptor::Encryptor, errors::ErrorKind, seekable::SeekableDecryptor, types::*,
+};
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+fn seekable(message: &[u8]) -> SeekableDecryptor<Cursor<&[u8]>> {
+    SeekableDecryptor::new(&decryptor(message), Cursor::new(message)).unwrap()
+}
+
+#[test]
+fn reads_the_whole_plain_text() {
+    for len in [0, 1, 15, 16, 17, 1000, 20000] {
+        let plain_text = plain_text(len);
+        let message = message(&plain_text);
+        let mut decrypted = Vec::new();
+        let mut seekable = seekable(&message);
+        seekable.read_to_end(&mut decrypted).unwrap();
+
+        assert_eq!(seekable.plain_text_len(), len as u64);
+        assert_eq!(decrypted, plain_text, "{}", len);
+    }
+}
+
+#[test]
+fn reads_at_arbitrary_offsets() {
+    fn matches_slice(len: u16, offset: u16, count: u8) -> bool {
+        let plain_text = plain_text(len as usize);
+        let message = message(&plain_text);
+        let mut seekable = seekable(&message);
+        seekable.seek(SeekFrom::Start(offset as u64)).unwrap();
+        let mut read = vec![0; count as usize];
+        let read_len = seekable.read(&mut read).unwrap();
+
+        let start = (offset as usize).min(plain_text.len());
+        let end = (start + count as usize).min(plain_text.len());
+        read[..read_len] == plain_text[start..end]
+    }
+    QuickCheck::new()
+        .tests(100)
+        .quickcheck(matches_slice as fn(u16, u16, u8) -> bool);
+}
+
+#[test]
+fn seeks_relative_to_the_end() {
+    let plain_text = plain_text(100);
+    let message = message(&plain_text);
+    let mut seekable = seekable(&message);
+    assert_eq!(seekable.seek(SeekFrom::End(-10)).unwrap(), 90);
+    let mut tail = Vec::new();
+    seekable.read_to_end(&mut tail).unwrap();
+
+    assert_eq!(tail, plain_text[90..]);
+    assert!(seekable.seek(SeekFrom::Current(-101)).is_err());
+}
+
+#[test]
+fn starts_at_the_current_position() {
+    let plain_text = plain_text(100);
+    let mut stored = b"some prefix".to_vec();
+    stored.extend(message(&plain_text));
+    let mut inner = Cursor::new(&stored[..]);
+    inner.seek(SeekFrom::Start(11)).unwrap();
+    let mut seekable = SeekableDecryptor::new(&decryptor(&stored[11..]), inner).unwrap();
+    let mut decrypted = Vec::new();
+    seekable.read_to_end(&mut decrypted).unwrap();
+
+    assert_eq!(decrypted, plain_text);
+}
+
+#[test]
+fn rejects_tampered_messages() {
+    let mut message = message(&plain_text(100));
+    message[40] ^= 1;
+    let error = SeekableDecryptor::new(&decryptor(&message), Cursor::new(&message[..]));
+
+    assert!(matches!(
+        error.err().unwrap().kind,
+        ErrorKind::AuthenticationFailed
+    ));
+}
+
+#[test]
+fn trusts_messages_verified_earlier() {
+    let plain_text = plain_text(100);
+    let mut message = message(&plain_text);
+    // Damages the first block only, leaving the padding intact.
+    message[20] ^= 1;
+    let mut seekable =
+        SeekableDecryptor::verified_earlier(&decryptor(&message), Cursor::new(&message[..]))
+            .unwrap();
+    seekable.seek(SeekFrom::Start(32)).unwrap();
+    let mut tail = Vec::new();
+    seekable.read_to_end(&mut tail).unwrap();
+
+    assert_eq!(tail, plain_text[32..]);
+}
+
+#[test]
+fn rejects_decryptors_of_other_messages() {
+    let message = message(&plain_text(100));
+    let (encryption_key, hmac_key) = keys();
+    let other = Encryptor::from_keys_with_fixed_iv(encryption_key, hmac_key, IV::from([4; 16]))
+        .and_then(|e| e.encrypt(&plain_text(100)))
+        .unwrap();
+    let error = SeekableDecryptor::verified_earlier(&decryptor(&other), Cursor::new(&message[..]));
+
+    assert!(matches!(
+        error.err().unwrap().kind,
+        ErrorKind::AuthenticationFailed
+    ));
+}
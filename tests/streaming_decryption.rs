@@ -0,0 +1,137 @@
+mod common;
+
+use common::error_kind;
+use quickcheck::QuickCheck;
+use rncryptor::v3::{
+    self, encryptor::Encryptor, errors::ErrorKind, reader::DecryptingReader, types::*,
+};
+use std::io::{self, Read};
+
+fn decode_hex(hex: &str) -> Vec<u8> {
+    hex::decode(hex.replace(' ', "")).unwrap()
+}
+
+/// A reader handing out at most `chunk_size` bytes per `read`.
+struct Trickle<'a> {
+    data: &'a [u8],
+    chunk_size: usize,
+}
+
+impl<'a> Read for Trickle<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.chunk_size.min(buf.len()).min(self.data.len());
+        buf[..read].copy_from_slice(&self.data[..read]);
+        self.data = &self.data[read..];
+        Ok(read)
+    }
+}
+
+fn decrypt(password: &str, message: &[u8], chunk_size: usize) -> io::Result<Vec<u8>> {
+    let inner = Trickle {
+        data: message,
+        chunk_size,
+    };
+    let mut reader = DecryptingReader::from_password(password, inner)?;
+    let mut plain_text = Vec::new();
+    reader.read_to_end(&mut plain_text)?;
+    Ok(plain_text)
+}
+
+#[test]
+fn decrypts_password_test_vector() {
+    let message = decode_hex(
+        "03010203 04050607 00010304 05060708 01020405 06070809 0a0b0c0d 0e0f0001 \
+         0203e01b bda5df2c a8adace3 8f6c588d 291e03f9 51b78d34 17bc2816 581dc6b7 \
+         67f1a2e5 7597512b 18e1638f 21235fa5 928c",
+    );
+    let plain_text = decrypt("thepassword", &message, 5).unwrap();
+
+    assert_eq!(plain_text, decode_hex("0123456789abcdef 01234567"))
+}
+
+#[test]
+fn rejects_tampered_hmac() {
+    let mut message = v3::encrypt("password", b"some secret data").unwrap();
+    let last = message.len() - 1;
+    message[last] ^= 1;
+
+    let error = decrypt("password", &message, 64).unwrap_err();
+    assert!(matches!(
+        error_kind(&error),
+        ErrorKind::AuthenticationFailed
+    ))
+}
+
+#[test]
+fn rejects_truncated_message() {
+    let message = v3::encrypt("password", b"some secret data").unwrap();
+
+    let error = decrypt("password", &message[..60], 64).unwrap_err();
+    assert!(matches!(error_kind(&error), ErrorKind::NotEnoughInput(_)))
+}
+
+#[test]
+fn streaming_matches_decrypt() {
+    fn streamed_equals_decrypted(message: Vec<u8>, chunk_size: u8) -> bool {
+        let encryption_salt = Salt([1; 8]);
+        let hmac_salt = Salt([2; 8]);
+        let iv = IV::from([3; 16]);
+        let encrypted = Encryptor::from_password("secret", encryption_salt, hmac_salt, iv)
+            .and_then(|e| e.encrypt(&message))
+            .unwrap();
+        match decrypt("secret", &encrypted, chunk_size.max(1) as usize) {
+            Err(_) => false,
+            Ok(v) => v == message,
+        }
+    }
+    QuickCheck::new()
+        .tests(15)
+        .quickcheck(streamed_equals_decrypted as fn(Vec<u8>, u8) -> bool);
+}
+
+/// A reader which would block before handing out every chunk but the first (the header).
+struct Blocking<'a> {
+    data: &'a [u8],
+    ready: bool,
+}
+
+impl<'a> Read for Blocking<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.ready = !self.ready;
+        if !self.ready {
+            return Err(io::ErrorKind::WouldBlock.into());
+        }
+        let read = 34.min(buf.len()).min(self.data.len());
+        buf[..read].copy_from_slice(&self.data[..read]);
+        self.data = &self.data[read..];
+        Ok(read)
+    }
+}
+
+#[test]
+fn would_block_can_be_retried() {
+    let plain_text = (0..100).collect::<Vec<u8>>();
+    let message = v3::encrypt("password", &plain_text).unwrap();
+    let mut reader = DecryptingReader::from_password(
+        "password",
+        Blocking {
+            data: &message,
+            ready: false,
+        },
+    )
+    .unwrap();
+
+    let mut decrypted = Vec::new();
+    let mut buf = [0; 10];
+    let mut blocked = 0;
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(read) => decrypted.extend_from_slice(&buf[..read]),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => blocked += 1,
+            Err(e) => panic!("{:?}", e),
+        }
+    }
+    assert!(blocked > 0);
+    assert_eq!(decrypted, plain_text)
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use common::encryptor;
+use quickcheck::QuickCheck;
+use rncryptor::v3::{encryptor::Encryptor, types::*};
+use std::io::{self, Write};
+
+fn decode_hex(hex: &str) -> Vec<u8> {
+    hex::decode(hex.replace(' ', "")).unwrap()
+}
+
+fn stream_in_chunks(encryptor: &Encryptor, plain_text: &[u8], chunk_size: usize) -> Vec<u8> {
+    let mut writer = encryptor.writer(Vec::new()).unwrap();
+    for chunk in plain_text.chunks(chunk_size.max(1)) {
+        writer.write_all(chunk).unwrap();
+    }
+    writer.finish().unwrap()
+}
+
+/// A writer accepting at most 5 bytes at a time, which would block on every other one of its
+/// first few calls.
+#[derive(Default)]
+struct Flaky {
+    written: Vec<u8>,
+    calls: usize,
+}
+
+impl Write for Flaky {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.calls += 1;
+        if self.calls <= 20 && self.calls % 2 == 1 {
+            return Err(io::ErrorKind::WouldBlock.into());
+        }
+        let written = buf.len().min(5);
+        self.written.extend_from_slice(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn matches_key_based_test_vector() {
+    let encryption_key =
+        EncryptionKey::from_hex("02030405060708090a0b0c0d0e0f000102030405060708090a0b0c0d0e0f0001")
+            .unwrap();
+    let hmac_key =
+        HMACKey::from_hex("030405060708090a0b0c0d0e0f000102030405060708090a0b0c0d0e0f000102")
+            .unwrap();
+    let iv = IV::from_hex("0405060708090a0b0c0d0e0f00010203").unwrap();
+    let plain_text = decode_hex("000102030405060708090a0b0c0d0e0f 000102030405060708");
+    let expected = decode_hex(
+        "03000405 06070809 0a0b0c0d 0e0f0001 02034c9b 98b425f1 d732644c b311278d \
+         858e3d18 2a0789b8 6af7f741 34b6a27e 9d938617 741c0fb8 aaf094b3 b5b26f50 \
+         5da7bf19 13f6c17e 70273977 ae51323b 6f09",
+    );
+    let encryptor = Encryptor::from_keys_with_fixed_iv(encryption_key, hmac_key, iv).unwrap();
+
+    assert_eq!(stream_in_chunks(&encryptor, &plain_text, 3), expected)
+}
+
+#[test]
+fn empty_input_matches_encrypt() {
+    let encryptor = encryptor();
+    let streamed = encryptor.writer(Vec::new()).unwrap().finish();
+
+    assert_eq!(streamed.unwrap(), encryptor.encrypt(&[]).unwrap())
+}
+
+#[test]
+fn streaming_matches_encrypt() {
+    fn streamed_equals_encrypted(message: Vec<u8>, chunk_size: u8) -> bool {
+        let encryptor = encryptor();
+        let streamed = stream_in_chunks(&encryptor, &message, chunk_size as usize);
+        streamed == encryptor.encrypt(&message).unwrap()
+    }
+    QuickCheck::new()
+        .tests(100)
+        .quickcheck(streamed_equals_encrypted as fn(Vec<u8>, u8) -> bool);
+}
+
+#[test]
+fn retrying_after_a_failed_write_encrypts_once() {
+    let encryptor = encryptor();
+    let plain_text = (0..100).collect::<Vec<u8>>();
+    let mut writer = encryptor.writer(Flaky::default()).unwrap();
+    for chunk in plain_text.chunks(7) {
+        loop {
+            match writer.write(chunk) {
+                Ok(written) => {
+                    assert_eq!(written, chunk.len());
+                    break;
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => continue,
+                Err(e) => panic!("{:?}", e),
+            }
+        }
+    }
+    while writer.flush().is_err() {}
+
+    let Flaky { written, calls } = writer.finish().unwrap();
+    assert!(calls > 20);
+    assert_eq!(written, encryptor.encrypt(&plain_text).unwrap())
+}
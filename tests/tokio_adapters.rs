@@ -0,0 +1,125 @@
+#![cfg(feature = "tokio")]
+
+mod common;
+
+use common::{encryptor, error_kind, keys};
+use rncryptor::v3::{
+    self,
+    async_tokio::{derive_keys, AsyncDecryptReader, AsyncEncryptWriter},
+    encryptor::Encryptor,
+    errors::ErrorKind,
+    types::*,
+};
+use std::num::NonZeroU32;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+#[tokio::test]
+async fn writing_matches_encrypt() {
+    let encryptor = encryptor();
+    let plain_text: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+    let mut writer = AsyncEncryptWriter::new(&encryptor, Vec::new()).unwrap();
+    for chunk in plain_text.chunks(37) {
+        writer.write_all(chunk).await.unwrap();
+    }
+    writer.shutdown().await.unwrap();
+
+    assert_eq!(*writer.get_ref(), encryptor.encrypt(&plain_text).unwrap());
+}
+
+#[tokio::test]
+async fn reads_key_based_messages() {
+    let plain_text: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+    let message = encryptor().encrypt(&plain_text).unwrap();
+    let (encryption_key, hmac_key) = keys();
+    let mut reader = AsyncDecryptReader::with_keys(encryption_key, hmac_key, &message[..]);
+    let mut decrypted = Vec::new();
+    reader.read_to_end(&mut decrypted).await.unwrap();
+
+    assert_eq!(decrypted, plain_text);
+}
+
+#[tokio::test]
+async fn rejects_tampered_messages() {
+    let mut message = encryptor().encrypt(b"some secret data").unwrap();
+    let last = message.len() - 1;
+    message[last] ^= 1;
+    let (encryption_key, hmac_key) = keys();
+    let mut reader = AsyncDecryptReader::with_keys(encryption_key, hmac_key, &message[..]);
+    let error = reader.read_to_end(&mut Vec::new()).await.unwrap_err();
+
+    assert!(matches!(
+        error_kind(&error),
+        ErrorKind::AuthenticationFailed
+    ));
+}
+
+#[tokio::test]
+async fn streams_password_based_messages_through_a_pipe() {
+    let plain_text: Vec<u8> = (0..5000).map(|i| (i % 251) as u8).collect();
+    // A tiny pipe, so that both sides keep waiting on each other.
+    let (client, server) = tokio::io::duplex(64);
+
+    let to_send = plain_text.clone();
+    let sender = tokio::spawn(async move {
+        let mut writer = AsyncEncryptWriter::from_password("thepassword", client)
+            .await
+            .unwrap();
+        writer.write_all(&to_send).await.unwrap();
+        writer.shutdown().await.unwrap();
+    });
+    let mut reader = AsyncDecryptReader::from_password("thepassword", server)
+        .await
+        .unwrap();
+    let mut received = Vec::new();
+    reader.read_to_end(&mut received).await.unwrap();
+    sender.await.unwrap();
+
+    assert_eq!(received, plain_text);
+}
+
+#[tokio::test]
+async fn non_standard_params_round_trip() {
+    let params = KdfParams::non_standard(NonZeroU32::new(2).unwrap(), Prf::HmacSha256);
+    let mut writer =
+        AsyncEncryptWriter::from_password_with_params("thepassword", Vec::new(), &params)
+            .await
+            .unwrap();
+    writer.write_all(b"secret").await.unwrap();
+    writer.shutdown().await.unwrap();
+    let message = writer.get_ref().clone();
+
+    let mut reader =
+        AsyncDecryptReader::from_password_with_params("thepassword", &message[..], &params)
+            .await
+            .unwrap();
+    let mut received = Vec::new();
+    reader.read_to_end(&mut received).await.unwrap();
+    assert_eq!(received, b"secret");
+    assert!(v3::decrypt("thepassword", &message).is_err());
+}
+
+#[tokio::test]
+async fn offloaded_keys_match_blocking_ones() {
+    let (encryption_salt, hmac_salt) = (Salt([1; 8]), Salt([2; 8]));
+    let (encryption_key, hmac_key) = derive_keys(
+        "thepassword",
+        encryption_salt.clone(),
+        hmac_salt.clone(),
+        &KdfParams::default(),
+    )
+    .await
+    .unwrap();
+    let message = Encryptor::from_keys_with_fixed_iv(encryption_key, hmac_key, IV::from([3; 16]))
+        .and_then(|e| e.encrypt(b"secret"))
+        .unwrap();
+
+    assert_eq!(
+        v3::decrypt_with_keys(
+            EncryptionKey::new(&encryption_salt, b"thepassword"),
+            HMACKey::new(&hmac_salt, b"thepassword"),
+            &message
+        )
+        .unwrap(),
+        b"secret".to_vec()
+    );
+}
@@ -0,0 +1,155 @@
+//! The v2 messages are assembled by hand out of the primitives (AES-256-CBC from the v3
+//! `Encryptor`, HMAC-SHA256 straight from the `hmac` crate), following the v2 spec: same layout
+//! as v3, with a version byte of 2.
+//!
+//! The known-answer vectors at the bottom don't go through this crate at all. The ASCII one is
+//! the "More than one block" v3 spec vector with its version byte set to 2 (the cipher text is
+//! the spec's, untouched) and its HMAC recomputed with Python's `hashlib` and `hmac`. The
+//! multibyte one reuses the salts, `IV` and plain text of the "Multibyte password" v3 spec
+//! vector, with keys derived out of the first 4 bytes of the password by `hashlib` and AES from
+//! Python's `cryptography`.
+use hmac::{Hmac, Mac};
+use rncryptor::{
+    v2,
+    v3::{encryptor::Encryptor, errors::ErrorKind, types::*},
+};
+use sha2::Sha256;
+
+fn decode_hex(hex: &str) -> Vec<u8> {
+    hex::decode(hex.replace(' ', "")).unwrap()
+}
+
+fn v2_message(
+    header: Vec<u8>,
+    encryption_key: EncryptionKey,
+    hmac_key: &HMACKey,
+    iv: IV,
+    plain_text: &[u8],
+) -> Vec<u8> {
+    let CipherText(cipher_text) =
+        Encryptor::from_keys_with_fixed_iv(encryption_key, hmac_key.clone(), iv)
+            .and_then(|e| e.cipher_text_pkcs7(plain_text))
+            .unwrap();
+    let mut message = header;
+    message.extend(cipher_text);
+    let mut mac = Hmac::<Sha256>::new_from_slice(hmac_key.as_slice()).unwrap();
+    mac.update(&message);
+    message.extend(mac.finalize().into_bytes());
+    message
+}
+
+fn password_message(password_bytes: &[u8], plain_text: &[u8]) -> Vec<u8> {
+    let (encryption_salt, hmac_salt, iv) = (Salt([1; 8]), Salt([2; 8]), IV::from([3; 16]));
+    let mut header = vec![2, 1];
+    header.extend(encryption_salt.as_slice());
+    header.extend(hmac_salt.as_slice());
+    header.extend(iv.as_slice());
+    v2_message(
+        header,
+        EncryptionKey::new(&encryption_salt, password_bytes),
+        &HMACKey::new(&hmac_salt, password_bytes),
+        iv,
+        plain_text,
+    )
+}
+
+#[test]
+fn decrypts_key_based_message() {
+    let iv = IV::from([3; 16]);
+    let mut header = vec![2, 0];
+    header.extend(iv.as_slice());
+    let message = v2_message(
+        header,
+        EncryptionKey::from([1; 32]),
+        &HMACKey::from([2; 32]),
+        iv,
+        b"a v2 message",
+    );
+    let decrypted = v2::decrypt_with_keys(
+        EncryptionKey::from([1; 32]),
+        HMACKey::from([2; 32]),
+        &message,
+    );
+
+    assert_eq!(decrypted.unwrap(), b"a v2 message".to_vec());
+}
+
+#[test]
+fn decrypts_password_based_message() {
+    let message = password_message(b"thepassword", b"a v2 message");
+
+    assert_eq!(
+        v2::decrypt("thepassword", &message).unwrap(),
+        b"a v2 message".to_vec()
+    );
+}
+
+#[test]
+fn reproduces_the_multibyte_password_truncation() {
+    // 4 characters, but 12 UTF-8 bytes: v2 only ever used the first 4 of them.
+    let password = "中文密码";
+    let message = password_message(&password.as_bytes()[..4], b"a v2 message");
+
+    assert_eq!(
+        v2::decrypt(password, &message).unwrap(),
+        b"a v2 message".to_vec()
+    );
+}
+
+#[test]
+fn truncation_only_applies_to_multibyte_passwords() {
+    let password = Password::from("thepassword");
+
+    assert_eq!(v2::decryptor::password_bytes(&password), b"thepassword");
+    assert_eq!(
+        v2::decryptor::password_bytes(&Password::from("中文密码")),
+        &"中文密码".as_bytes()[..4]
+    );
+}
+
+#[test]
+fn rejects_v3_messages() {
+    let message = Encryptor::from_keys_with_fixed_iv(
+        EncryptionKey::from([1; 32]),
+        HMACKey::from([2; 32]),
+        IV::from([3; 16]),
+    )
+    .and_then(|e| e.encrypt(b"a v3 message"))
+    .unwrap();
+    let error = v2::decrypt_with_keys(
+        EncryptionKey::from([1; 32]),
+        HMACKey::from([2; 32]),
+        &message,
+    )
+    .unwrap_err();
+
+    assert!(matches!(error.kind, ErrorKind::UnsupportedVersion(3)));
+}
+
+#[test]
+fn decrypts_known_answer_vector() {
+    let message = decode_hex(
+        "02010203 04050607 00010304 05060708 01020405 06070809 0a0b0c0d 0e0f0001 \
+         0203e01b bda5df2c a8adace3 8f6c588d 291e72a9 73677b38 6b21fd43 fcfe411a \
+         9460c604 207296a8 7707b8a7 2b690600 eaca",
+    );
+
+    assert_eq!(
+        v2::decrypt("thepassword", &message).unwrap(),
+        decode_hex("0123456789abcdef 01234567")
+    );
+}
+
+#[test]
+fn decrypts_known_answer_multibyte_vector() {
+    let message = decode_hex(
+        "02010304 05060700 01020405 06070801 02030506 0708090a 0b0c0d0e 0f000102 \
+         030453d0 24170a99 9c9f13cd d29a3f73 e4af0740 c60e981b 09839ec3 5c5c1a50 \
+         7f3c27b6 3527b5c4 c3789e6b baf6b1ff 729d",
+    );
+
+    assert_eq!(
+        v2::decrypt("中文密码", &message).unwrap(),
+        decode_hex("23456789abcdef 0123456701")
+    );
+}
@@ -0,0 +1,143 @@
+//! The expected v2 messages are assembled by hand out of independent primitives (the `cbc` crate
+//! for AES-256-CBC, the `hmac` crate for HMAC-SHA256), following the v2 spec.
+use aes::cipher::{block_padding::Pkcs7, BlockEncryptMut, KeyIvInit};
+use hmac::{Hmac, Mac};
+use rncryptor::{
+    v2::{self, encryptor::Encryptor},
+    v3::{self, errors::ErrorKind, types::*},
+    Version,
+};
+use sha2::Sha256;
+use std::io::Write;
+
+fn v2_message(
+    mut header: Vec<u8>,
+    encryption_key: &EncryptionKey,
+    hmac_key: &HMACKey,
+    iv: &IV,
+    plain_text: &[u8],
+) -> Vec<u8> {
+    header.extend(
+        cbc::Encryptor::<aes::Aes256>::new(encryption_key.as_slice().into(), iv.as_slice().into())
+            .encrypt_padded_vec_mut::<Pkcs7>(plain_text),
+    );
+    let mut mac = Hmac::<Sha256>::new_from_slice(hmac_key.as_slice()).unwrap();
+    mac.update(&header);
+    header.extend(mac.finalize().into_bytes());
+    header
+}
+
+fn key_encryptor() -> Encryptor {
+    Encryptor::from_keys_with_fixed_iv(
+        EncryptionKey::from([1; 32]),
+        HMACKey::from([2; 32]),
+        IV::from([3; 16]),
+    )
+    .unwrap()
+}
+
+#[test]
+fn key_based_message_is_byte_exact() {
+    let mut header = vec![2, 0];
+    header.extend([3; 16]);
+    let expected = v2_message(
+        header,
+        &EncryptionKey::from([1; 32]),
+        &HMACKey::from([2; 32]),
+        &IV::from([3; 16]),
+        b"a v2 message",
+    );
+
+    assert_eq!(key_encryptor().encrypt(b"a v2 message").unwrap(), expected);
+}
+
+#[test]
+fn password_based_message_is_byte_exact() {
+    // 4 characters, but 12 UTF-8 bytes: v2 clients only ever use the first 4 of them.
+    let password = "中文密码";
+    let (encryption_salt, hmac_salt, iv) = (Salt([1; 8]), Salt([2; 8]), IV::from([3; 16]));
+    let mut header = vec![2, 1];
+    header.extend(encryption_salt.as_slice());
+    header.extend(hmac_salt.as_slice());
+    header.extend(iv.as_slice());
+    let expected = v2_message(
+        header,
+        &EncryptionKey::new(&encryption_salt, &password.as_bytes()[..4]),
+        &HMACKey::new(&hmac_salt, &password.as_bytes()[..4]),
+        &iv,
+        b"a v2 message",
+    );
+    let encryptor = Encryptor::from_password(password, encryption_salt, hmac_salt, iv).unwrap();
+
+    assert_eq!(encryptor.encrypt(b"a v2 message").unwrap(), expected);
+}
+
+#[test]
+fn round_trips_through_the_v2_decryptor() {
+    let message = v2::encrypt("thepassword", b"a v2 message").unwrap();
+
+    assert_eq!(message[..2], [2, 1]);
+    assert_eq!(
+        v2::decrypt("thepassword", &message).unwrap(),
+        b"a v2 message".to_vec()
+    );
+}
+
+#[test]
+fn streaming_matches_encrypt() {
+    let encryptor = key_encryptor();
+    let mut writer = encryptor.writer(Vec::new()).unwrap();
+    for chunk in [0; 100].chunks(7) {
+        writer.write_all(chunk).unwrap();
+    }
+
+    assert_eq!(
+        writer.finish().unwrap(),
+        encryptor.encrypt(&[0; 100]).unwrap()
+    );
+}
+
+#[test]
+fn is_not_mistaken_for_v3() {
+    let message = key_encryptor().encrypt(b"a v2 message").unwrap();
+    let error = v3::decrypt_with_keys(
+        EncryptionKey::from([1; 32]),
+        HMACKey::from([2; 32]),
+        &message,
+    )
+    .unwrap_err();
+
+    assert!(matches!(error.kind, ErrorKind::UnsupportedVersion(2)));
+}
+
+#[test]
+fn can_be_picked_as_the_write_format() {
+    let message = rncryptor::encrypt_with_keys(
+        Version::V2,
+        EncryptionKey::from([1; 32]),
+        HMACKey::from([2; 32]),
+        b"a v2 message",
+    )
+    .unwrap();
+
+    assert_eq!(Version::of(&message).unwrap(), Version::V2);
+    assert_eq!(
+        rncryptor::decrypt_with_keys(
+            EncryptionKey::from([1; 32]),
+            HMACKey::from([2; 32]),
+            &message
+        )
+        .unwrap(),
+        b"a v2 message".to_vec()
+    );
+}
+
+#[test]
+fn rejects_empty_passwords() {
+    let error = Encryptor::from_password("", Salt([1; 8]), Salt([2; 8]), IV::from([3; 16]));
+
+    assert!(matches!(
+        error.unwrap_err().kind,
+        ErrorKind::WrongInputSize(0)
+    ));
+}
@@ -0,0 +1,136 @@
+mod common;
+
+use common::{authenticated_message_with_bad_padding, decryptor, keys, message};
+use rncryptor::v3::{
+    self,
+    encryptor::{Encryptor, ReusableEncryptor},
+    errors::ErrorKind,
+    types::*,
+};
+use std::{io::Read, num::NonZeroU32};
+
+/// A reader handing out a few bytes at a time.
+struct Trickle<'a>(&'a [u8]);
+
+impl<'a> Read for Trickle<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = 7.min(buf.len()).min(self.0.len());
+        buf[..read].copy_from_slice(&self.0[..read]);
+        self.0 = &self.0[read..];
+        Ok(read)
+    }
+}
+
+#[test]
+fn accepts_authentic_messages() {
+    let message = message(&[7; 20000]);
+    let decryptor = decryptor(&message);
+
+    assert!(decryptor.verify(&message).is_ok());
+    assert!(decryptor.verify_reader(&message[..]).is_ok());
+    assert!(decryptor.verify_reader(Trickle(&message)).is_ok());
+}
+
+#[test]
+fn accepts_authentic_password_based_messages() {
+    let message = v3::encrypt("thepassword", b"some secret data").unwrap();
+
+    assert!(v3::verify("thepassword", &message).is_ok());
+    assert!(matches!(
+        v3::verify("wrongpassword", &message).unwrap_err().kind,
+        ErrorKind::AuthenticationFailed
+    ));
+}
+
+#[test]
+fn streams_password_based_messages() {
+    let params = KdfParams::non_standard(NonZeroU32::new(2).unwrap(), Prf::HmacSha1);
+    let message = Encryptor::from_password_with_params(
+        "thepassword",
+        Salt([1; 8]),
+        Salt([2; 8]),
+        IV::from([3; 16]),
+        &params,
+    )
+    .and_then(|e| e.encrypt(&[7; 20000]))
+    .unwrap();
+
+    assert!(v3::verify_reader_with_params("thepassword", Trickle(&message), &params).is_ok());
+    assert!(matches!(
+        v3::verify_reader_with_params("wrongpassword", Trickle(&message), &params)
+            .unwrap_err()
+            .kind,
+        ErrorKind::AuthenticationFailed
+    ));
+
+    let mut rotten = message.clone();
+    rotten[100] ^= 0x04;
+    assert!(v3::verify_reader_with_params("thepassword", Trickle(&rotten), &params).is_err());
+    assert!(matches!(
+        v3::verify_reader_with_params("thepassword", Trickle(&message[..30]), &params)
+            .unwrap_err()
+            .kind,
+        ErrorKind::NotEnoughInput(30)
+    ));
+}
+
+#[test]
+fn streams_password_based_messages_with_the_default_params() {
+    let message = v3::encrypt("thepassword", b"some secret data").unwrap();
+
+    assert!(v3::verify_reader("thepassword", &message[..]).is_ok());
+}
+
+#[test]
+fn detects_bit_rot_anywhere() {
+    let message = message(b"a plain text spanning a couple of blocks");
+    let decryptor = decryptor(&message);
+    for position in 0..message.len() {
+        let mut rotten = message.clone();
+        rotten[position] ^= 0x04;
+
+        assert!(decryptor.verify(&rotten).is_err(), "{}", position);
+        assert!(
+            decryptor.verify_reader(Trickle(&rotten)).is_err(),
+            "{}",
+            position
+        );
+    }
+}
+
+#[test]
+fn rejects_other_messages_like_decrypt() {
+    // Another authentic message under the same keys, but with a different IV.
+    let (encryption_key, hmac_key) = keys();
+    let other = ReusableEncryptor::from_keys(encryption_key, hmac_key)
+        .encrypt(b"another plain text")
+        .unwrap();
+    let decryptor = decryptor(&message(b"a plain text"));
+
+    for error in [
+        decryptor.verify(&other).unwrap_err(),
+        decryptor.verify_reader(&other[..]).unwrap_err(),
+        decryptor.decrypt(&other).unwrap_err(),
+    ] {
+        assert!(matches!(error.kind, ErrorKind::AuthenticationFailed));
+    }
+}
+
+#[test]
+fn detects_truncation() {
+    let message = message(&[7; 100]);
+    let decryptor = decryptor(&message);
+    for len in 0..message.len() {
+        assert!(decryptor.verify(&message[..len]).is_err(), "{}", len);
+        assert!(decryptor.verify_reader(&message[..len]).is_err(), "{}", len);
+    }
+}
+
+#[test]
+fn skips_decryption() {
+    // A valid HMAC over an invalid padding: only decrypting would notice.
+    let (encryption_key, hmac_key) = keys();
+    let message = authenticated_message_with_bad_padding(16);
+
+    assert!(v3::verify_with_keys(encryption_key, hmac_key, &message).is_ok());
+}
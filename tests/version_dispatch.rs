@@ -0,0 +1,93 @@
+mod common;
+
+use common::keys;
+use hmac::{Hmac, Mac};
+use rncryptor::{
+    self,
+    v3::{self, encryptor::Encryptor, errors::ErrorKind, types::*},
+    Version,
+};
+use sha2::Sha256;
+
+/// A key-based v2 message, which only differs from a v3 one by its version byte (and HMAC).
+fn v2_message(plain_text: &[u8]) -> Vec<u8> {
+    let (encryption_key, hmac_key) = keys();
+    let iv = IV::from([3; 16]);
+    let CipherText(cipher_text) =
+        Encryptor::from_keys_with_fixed_iv(encryption_key, hmac_key.clone(), iv.clone())
+            .and_then(|e| e.cipher_text_pkcs7(plain_text))
+            .unwrap();
+    let mut message = vec![2, 0];
+    message.extend(iv.as_slice());
+    message.extend(cipher_text);
+    let mut mac = Hmac::<Sha256>::new_from_slice(hmac_key.as_slice()).unwrap();
+    mac.update(&message);
+    message.extend(mac.finalize().into_bytes());
+    message
+}
+
+#[test]
+fn reads_the_version_byte() {
+    assert_eq!(Version::of(&[3, 1]).unwrap(), Version::V3);
+    assert_eq!(Version::of(&[0]).unwrap(), Version::V0);
+    assert!(matches!(
+        Version::of(&[]).unwrap_err().kind,
+        ErrorKind::NotEnoughInput(0)
+    ));
+    assert_eq!(Version::default(), Version::LATEST);
+}
+
+#[test]
+fn dispatches_v3_messages() {
+    let message = v3::encrypt("password", b"secret").unwrap();
+
+    assert_eq!(
+        rncryptor::decrypt("password", &message).unwrap(),
+        b"secret".to_vec()
+    );
+}
+
+#[test]
+fn dispatches_v2_messages() {
+    let (encryption_key, hmac_key) = keys();
+    let message = v2_message(b"secret");
+
+    assert_eq!(
+        rncryptor::decrypt_with_keys(encryption_key, hmac_key, &message).unwrap(),
+        b"secret".to_vec()
+    );
+}
+
+#[test]
+fn encrypts_in_the_requested_version() {
+    let (encryption_key, hmac_key) = keys();
+    let message = rncryptor::encrypt_with_keys(
+        Version::V3,
+        encryption_key.clone(),
+        hmac_key.clone(),
+        b"secret",
+    )
+    .unwrap();
+
+    assert_eq!(Version::of(&message).unwrap(), Version::V3);
+    assert_eq!(
+        rncryptor::decrypt_with_keys(encryption_key, hmac_key, &message).unwrap(),
+        b"secret".to_vec()
+    );
+}
+
+#[test]
+fn rejects_unknown_versions() {
+    let (encryption_key, hmac_key) = keys();
+    let error = rncryptor::decrypt_with_keys(encryption_key, hmac_key, &[9, 0, 0]).unwrap_err();
+
+    assert!(matches!(error.kind, ErrorKind::UnsupportedVersion(9)));
+}
+
+#[test]
+fn rejects_writing_read_only_versions() {
+    let error = rncryptor::encrypt(Version::V1, "password", b"secret").unwrap_err();
+
+    assert!(matches!(error.kind, ErrorKind::UnsupportedVersion(1)));
+    assert!(!Version::V1.can_encrypt());
+}